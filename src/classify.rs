@@ -0,0 +1,150 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//What kind of address a MAC address is beyond its U/L and I/G bits: its quadrant of the
+//Structured Local Address Plan, and whether it is set aside for a protocol.
+
+use mac::MacAddress;
+
+//The Z and Y bits of the first octet, which split local addresses into the SLAP quadrants
+const SLAP_BITS: u8 = 0b1100;
+
+/// A quadrant of the IEEE 802c Structured Local Address Plan.  The Z and Y
+/// bits of the first octet, next to the U/L bit, sort locally administered
+/// addresses into four quadrants, seen in the second hex digit of the address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlapQuadrant {
+    /// Administratively Assigned Identifier, x2.  Assigned by a local
+    /// administrator, and the quadrant pmg hands out.
+    Aai,
+    /// Extended Local Identifier, xA.  Built on a Company ID from the IEEE.
+    Eli,
+    /// Standard Assigned Identifier, xE.  Assigned by a protocol standard.
+    Sai,
+    /// Reserved for future use, x6.
+    Reserved,
+}
+
+impl SlapQuadrant {
+    /// Returns the quadrant of a locally administered address, or None for a
+    /// universally administered one.  The I/G bit is not part of the quadrant.
+    ///
+    /// ```
+    /// extern crate pmg;
+    /// use pmg::{MacAddress, SlapQuadrant};
+    ///
+    /// let mac: MacAddress = "0a:5a:10:00:00:01".parse().unwrap();
+    /// assert_eq!(SlapQuadrant::of(&mac), Some(SlapQuadrant::Eli));
+    ///
+    /// let mac: MacAddress = "00:1b:21:00:00:01".parse().unwrap();
+    /// assert_eq!(SlapQuadrant::of(&mac), None);
+    /// ```
+    pub fn of(mac: &MacAddress) -> Option<SlapQuadrant> {
+        if !mac.is_local() {
+            return None;
+        }
+        Some(match mac.0[0] & SLAP_BITS {
+            0b0000 => SlapQuadrant::Aai,
+            0b1000 => SlapQuadrant::Eli,
+            0b1100 => SlapQuadrant::Sai,
+            _ => SlapQuadrant::Reserved,
+        })
+    }
+
+    /// Returns the short name of the quadrant, such as AAI.
+    pub fn abbreviation(&self) -> &'static str {
+        match *self {
+            SlapQuadrant::Aai => "AAI",
+            SlapQuadrant::Eli => "ELI",
+            SlapQuadrant::Sai => "SAI",
+            SlapQuadrant::Reserved => "Reserved",
+        }
+    }
+
+    /// Returns the full name of the quadrant.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            SlapQuadrant::Aai => "Administratively Assigned Identifier",
+            SlapQuadrant::Eli => "Extended Local Identifier",
+            SlapQuadrant::Sai => "Standard Assigned Identifier",
+            SlapQuadrant::Reserved => "Reserved",
+        }
+    }
+
+    /// Returns the second hex digit every unicast address in the quadrant has.
+    pub fn digit(&self) -> char {
+        match *self {
+            SlapQuadrant::Aai => '2',
+            SlapQuadrant::Eli => 'A',
+            SlapQuadrant::Sai => 'E',
+            SlapQuadrant::Reserved => '6',
+        }
+    }
+}
+
+//Addresses set aside by the IEEE, the IANA and vendors for a protocol, as the first and last
+//address of each block.  Single addresses come before the blocks that hold them so the more
+//specific name is found first.
+const WELL_KNOWN: &[([u8; 6], [u8; 6], &str)] = &[
+    ([0x01, 0x80, 0xc2, 0x00, 0x00, 0x00], [0x01, 0x80, 0xc2, 0x00, 0x00, 0x00], "IEEE 802.1 Nearest Customer Bridge, STP"),
+    ([0x01, 0x80, 0xc2, 0x00, 0x00, 0x01], [0x01, 0x80, 0xc2, 0x00, 0x00, 0x01], "IEEE 802.3 MAC Control, pause frames"),
+    ([0x01, 0x80, 0xc2, 0x00, 0x00, 0x02], [0x01, 0x80, 0xc2, 0x00, 0x00, 0x02], "IEEE 802.3 Slow Protocols, LACP"),
+    ([0x01, 0x80, 0xc2, 0x00, 0x00, 0x03], [0x01, 0x80, 0xc2, 0x00, 0x00, 0x03], "IEEE 802.1X Port Access Entity"),
+    ([0x01, 0x80, 0xc2, 0x00, 0x00, 0x0e], [0x01, 0x80, 0xc2, 0x00, 0x00, 0x0e], "IEEE 802.1 Nearest Bridge, LLDP and PTP peer delay"),
+    ([0x01, 0x80, 0xc2, 0x00, 0x00, 0x00], [0x01, 0x80, 0xc2, 0x00, 0x00, 0x0f], "IEEE 802.1 link local group address"),
+    ([0x01, 0x80, 0xc2, 0x00, 0x00, 0x10], [0x01, 0x80, 0xc2, 0x00, 0x00, 0x10], "IEEE 802.1 All LANs Bridge Management"),
+    ([0x01, 0x80, 0xc2, 0x00, 0x00, 0x20], [0x01, 0x80, 0xc2, 0x00, 0x00, 0x2f], "IEEE 802.1 MRP and GARP"),
+    ([0x01, 0x80, 0xc2, 0x00, 0x00, 0x30], [0x01, 0x80, 0xc2, 0x00, 0x00, 0x3f], "IEEE 802.1Q Connectivity Fault Management"),
+    ([0x01, 0x1b, 0x19, 0x00, 0x00, 0x00], [0x01, 0x1b, 0x19, 0x00, 0x00, 0x00], "IEEE 1588 Precision Time Protocol"),
+    ([0x01, 0x00, 0x5e, 0x90, 0x10, 0x00], [0x01, 0x00, 0x5e, 0x90, 0x10, 0xff], "IANA multicast for documentation, RFC 7042"),
+    ([0x01, 0x00, 0x5e, 0x00, 0x00, 0x00], [0x01, 0x00, 0x5e, 0x7f, 0xff, 0xff], "IANA IPv4 multicast, RFC 1112"),
+    ([0x00, 0x00, 0x5e, 0x00, 0x53, 0x00], [0x00, 0x00, 0x5e, 0x00, 0x53, 0xff], "IANA unicast for documentation, RFC 7042"),
+    ([0x00, 0x00, 0x5e, 0x00, 0x01, 0x00], [0x00, 0x00, 0x5e, 0x00, 0x01, 0xff], "IANA VRRP for IPv4, RFC 5798"),
+    ([0x00, 0x00, 0x5e, 0x00, 0x02, 0x00], [0x00, 0x00, 0x5e, 0x00, 0x02, 0xff], "IANA VRRP for IPv6, RFC 5798"),
+    ([0x33, 0x33, 0x00, 0x00, 0x00, 0x00], [0x33, 0x33, 0xff, 0xff, 0xff, 0xff], "IPv6 multicast, RFC 2464"),
+    ([0x00, 0x00, 0x0c, 0x07, 0xac, 0x00], [0x00, 0x00, 0x0c, 0x07, 0xac, 0xff], "Cisco HSRP version 1"),
+    ([0x00, 0x00, 0x0c, 0x9f, 0xf0, 0x00], [0x00, 0x00, 0x0c, 0x9f, 0xff, 0xff], "Cisco HSRP version 2"),
+    ([0x01, 0x00, 0x0c, 0xcc, 0xcc, 0xcc], [0x01, 0x00, 0x0c, 0xcc, 0xcc, 0xcc], "Cisco CDP, VTP and DTP"),
+    ([0x01, 0x00, 0x0c, 0xcc, 0xcc, 0xcd], [0x01, 0x00, 0x0c, 0xcc, 0xcc, 0xcd], "Cisco Shared Spanning Tree, PVST+"),
+    ([0x01, 0x0c, 0xcd, 0x01, 0x00, 0x00], [0x01, 0x0c, 0xcd, 0x01, 0x01, 0xff], "IEC 61850 GOOSE"),
+];
+
+impl MacAddress {
+    /// Returns the protocol or purpose the address is set aside for, such
+    /// as LLDP or VRRP, or None if it is not a well-known address.  The
+    /// broadcast and all-zero addresses are named too.
+    ///
+    /// ```
+    /// extern crate pmg;
+    /// use pmg::MacAddress;
+    ///
+    /// let mac: MacAddress = "01:80:c2:00:00:0e".parse().unwrap();
+    /// assert!(mac.well_known().unwrap().contains("LLDP"));
+    ///
+    /// let mac: MacAddress = "02:5a:10:00:00:01".parse().unwrap();
+    /// assert_eq!(mac.well_known(), None);
+    /// ```
+    pub fn well_known(&self) -> Option<&'static str> {
+        if self.is_broadcast() {
+            return Some("Broadcast");
+        }
+        if self.is_zero() {
+            return Some("All zero, an unset address");
+        }
+        //Six octets compare in the same order as the addresses they hold
+        WELL_KNOWN.iter()
+            .find(|&&(first, last, _)| first <= self.0 && self.0 <= last)
+            .map(|&(_, _, name)| name)
+    }
+}
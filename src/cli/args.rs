@@ -0,0 +1,276 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//The command line parser shared by every command.  Each command lists the options it takes,
+//and anything else on the command line is an error, reported on stderr with EXIT_USAGE,
+//rather than being ignored or replaced by a default.
+//
+//Options are written as POSIX -c upper, -cupper or -un, or as GNU --case upper or
+//--case=upper.  GNU names must be typed in full.  Options and values can come in any order,
+//and everything after -- is a value even if it starts with a dash.  An option used more than
+//once takes its last value, except for options such as -T/--tag that collect every value.
+
+use pmg::{Case, Notation};
+use std::io;
+use std::process;
+use std::str::FromStr;
+
+//Exit status when the command line could not be used: an unknown option, a missing value,
+//a value that is not accepted or options that cannot be used together
+pub const EXIT_USAGE: i32 = 2;
+
+//One option a command takes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Opt {
+    //The letter typed after a single dash, such as 'c' for -c
+    pub posix: char,
+    //The name typed after two dashes, such as "case" for --case
+    pub gnu: &'static str,
+    //Whether a value follows the option, such as upper in -c upper
+    pub takes_value: bool,
+}
+
+impl Opt {
+    //An option that is either used or not, such as -u/--unique
+    pub const fn flag(posix: char, gnu: &'static str) -> Opt {
+        Opt { posix, gnu, takes_value: false }
+    }
+
+    //An option followed by a value, such as -c/--case upper
+    pub const fn value(posix: char, gnu: &'static str) -> Opt {
+        Opt { posix, gnu, takes_value: true }
+    }
+
+    //How the option is named in messages, such as -c/--case
+    pub fn name(&self) -> String {
+        format!("-{}/--{}", self.posix, self.gnu)
+    }
+}
+
+//-h/--help, taken by every command
+pub const HELP: Opt = Opt::flag('h', "help");
+
+//The options that choose how addresses are written, see select_notation()
+pub const NOTATION: &[Opt] = &[
+    Opt::value('s', "separator"),
+    Opt::value('c', "case"),
+    Opt::value('g', "group-size"),
+    Opt::flag('n', "noSeparator"),
+];
+
+//A command line after parsing
+pub struct Args {
+    //The command named in usage errors, such as 'pmg range'
+    command: String,
+    options: Vec<Opt>,
+    //The values typed in that are not options, in order
+    pub positionals: Vec<String>,
+    //Every option used, in order, with its value if it takes one
+    used: Vec<(Opt, String)>,
+}
+
+impl Args {
+    //Parses `args`, the command line after the command's own name, against the options the
+    //command takes.  `command` is how the command is typed, such as 'pmg range', and names
+    //its help in errors.  An error stops the program.
+    pub fn parse(command: &str, options: &[Opt], args: &[String]) -> Args {
+        let mut parsed = Args {
+            command: command.to_string(),
+            options: options.to_vec(),
+            positionals: Vec::new(),
+            used: Vec::new(),
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                parsed.positionals.extend(args.by_ref().cloned());
+            } else if let Some(gnu) = arg.strip_prefix("--") {
+                //--name=value carries its value with it
+                let (name, inline) = match gnu.find('=') {
+                    Some(index) => (&gnu[..index], Some(&gnu[index + 1..])),
+                    None => (gnu, None),
+                };
+                let option = match parsed.options.iter().find(|option| option.gnu == name) {
+                    Some(option) => *option,
+                    None => parsed.usage(&format!("unknown option '--{}'", name)),
+                };
+
+                let value = match (option.takes_value, inline) {
+                    (true, Some(value)) => value.to_string(),
+                    (true, None) => match args.next() {
+                        Some(value) => value.clone(),
+                        None => parsed.usage(&format!("{} needs a value", option.name())),
+                    },
+                    (false, Some(_)) => parsed.usage(&format!("{} does not take a value", option.name())),
+                    (false, None) => String::new(),
+                };
+                parsed.used.push((option, value));
+            } else if arg.len() > 1 && arg.starts_with('-') {
+                //POSIX options can be run together, and the rest of the run after an option
+                //that takes a value is that value, so -un and -N5 both work
+                let letters = &arg[1..];
+                for (index, letter) in letters.char_indices() {
+                    let option = match parsed.options.iter().find(|option| option.posix == letter) {
+                        Some(option) => *option,
+                        None => parsed.usage(&format!("unknown option '-{}'", letter)),
+                    };
+                    if !option.takes_value {
+                        parsed.used.push((option, String::new()));
+                        continue;
+                    }
+
+                    let rest = &letters[index + letter.len_utf8()..];
+                    let value = if !rest.is_empty() {
+                        rest.to_string()
+                    } else {
+                        match args.next() {
+                            Some(value) => value.clone(),
+                            None => parsed.usage(&format!("{} needs a value", option.name())),
+                        }
+                    };
+                    parsed.used.push((option, value));
+                    break;
+                }
+            } else {
+                //A dash on its own is a value, such as standard input
+                parsed.positionals.push(arg.clone());
+            }
+        }
+
+        parsed
+    }
+
+    //Looks up an option by its GNU name.  Asking for an option the command does not take is
+    //a mistake in pmg rather than on the command line.
+    fn option(&self, gnu: &str) -> Opt {
+        match self.options.iter().find(|option| option.gnu == gnu) {
+            Some(option) => *option,
+            None => panic!("--{} is not an option of {}", gnu, self.command),
+        }
+    }
+
+    //Whether the option was used
+    pub fn flag(&self, gnu: &str) -> bool {
+        let option = self.option(gnu);
+        self.used.iter().any(|&(used, _)| used == option)
+    }
+
+    //The value of the option the last time it was used, or None if it was not used
+    pub fn value(&self, gnu: &str) -> Option<&str> {
+        let option = self.option(gnu);
+        self.used.iter().rev()
+            .find(|&&(used, _)| used == option)
+            .map(|(_, value)| value.as_str())
+    }
+
+    //The value of every use of the option, for options such as -T/--tag
+    pub fn values(&self, gnu: &str) -> Vec<&str> {
+        let option = self.option(gnu);
+        self.used.iter()
+            .filter(|&&(used, _)| used == option)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    //Parses the value of the option, stopping with an error that describes the `expected`
+    //values if it cannot be parsed
+    pub fn parse_value<T: FromStr>(&self, gnu: &str, expected: &str) -> Option<T> {
+        let value = self.value(gnu)?;
+        match value.parse() {
+            Ok(parsed) => Some(parsed),
+            Err(_) => self.invalid(gnu, value, expected),
+        }
+    }
+
+    //Looks the value of the option up in `choices`, stopping with an error that lists them if
+    //it is not one of them
+    pub fn choice<T: Clone>(&self, gnu: &str, choices: &[(&str, T)]) -> Option<T> {
+        let value = self.value(gnu)?;
+        match choices.iter().find(|&&(name, _)| name == value) {
+            Some((_, choice)) => Some(choice.clone()),
+            None => {
+                //Listed as a, b or c
+                let names: Vec<&str> = choices.iter().map(|&(name, _)| name).collect();
+                let expected = match names.split_last() {
+                    Some((last, [])) => last.to_string(),
+                    Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+                    None => String::new(),
+                };
+                self.invalid(gnu, value, &expected)
+            }
+        }
+    }
+
+    //Stops with an error for a value of the option that cannot be used
+    pub fn invalid(&self, gnu: &str, value: &str, expected: &str) -> ! {
+        self.usage(&format!("invalid {} '{}': expected {}", self.option(gnu).name(), value, expected))
+    }
+
+    //Prints a usage error pointing at the command's help and stops
+    pub fn usage(&self, message: &str) -> ! {
+        usage(&self.command, message)
+    }
+}
+
+//Prints a usage error pointing at the help of `command`, such as 'pmg range', and stops
+pub fn usage(command: &str, message: &str) -> ! {
+    eprintln!("pmg: {}", message);
+    eprintln!("Type '{} -h' for more information.", command);
+    process::exit(EXIT_USAGE);
+}
+
+//Ends a command once its output is written.  A closed pipe, such as when piping into head, is
+//not worth reporting, and any other error is printed and exits with 1.
+pub fn finish(result: io::Result<()>) {
+    if let Err(error) = result {
+        if !closed_pipe(&error) {
+            eprintln!("pmg: {}", error);
+            process::exit(1);
+        }
+    }
+}
+
+//Whether writing failed only because whatever reads the output has stopped reading
+pub fn closed_pipe(error: &io::Error) -> bool {
+    error.kind() == io::ErrorKind::BrokenPipe
+}
+
+//Reads how addresses are written out from the -c/--case, -n/--noSeparator, -s/--separator and
+//-g/--group-size arguments.  Shared by every command that prints addresses.
+pub fn select_notation(parsed_args: &Args) -> Notation {
+    //Provide what case the letters are to be displayed in.
+    //Default is lower case.
+    let case = parsed_args.choice("case", &[
+            ("l", Case::Lower),
+            ("u", Case::Upper),
+            ("lower", Case::Lower),
+            ("upper", Case::Upper),
+        ]).unwrap_or(Case::Lower);
+
+    //The separator is bypassed entirely when no_separator is used
+    let no_separator = parsed_args.flag("noSeparator");
+    let separator = parsed_args.choice("separator", &[(":", ':'), ("-", '-'), (".", '.')]).unwrap_or(':');
+
+    //How many octets are printed together between separators.  Cisco's dotted notation
+    //groups the address into three 16 bit words, so a dot defaults to groups of two.
+    let group = parsed_args.choice("group-size", &[("1", 1), ("2", 2), ("3", 3)])
+        .unwrap_or(if separator == '.' { 2 } else { 1 });
+
+    Notation {
+        separator: if no_separator { None } else { Some(separator) },
+        case,
+        group,
+    }
+}
@@ -0,0 +1,328 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//The audit log of a pool.  Every change to a pool appends one JSON line per address it
+//touched to a file named after the pool with .log added, so who held an address and when
+//can be answered long after it was released.
+
+use pmg::{Allocation, MacAddress, MacPrefix, Pool, PoolError};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use cli::json::Json;
+use cli::time::{format_time, now, parse_time};
+
+//One line of the audit log
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event {
+    //When the change was made, in seconds since the Unix epoch
+    pub time: u64,
+    //init, alloc, renew, release or reap
+    pub op: String,
+    //The prefix of a pool, for init only
+    pub prefix: Option<MacPrefix>,
+    //The address changed, for every operation but init
+    pub mac: Option<MacAddress>,
+    pub owner: Option<String>,
+    pub tags: BTreeMap<String, String>,
+    //When the lease runs out after the change, if it has one
+    pub expires: Option<u64>,
+    //Who made the change, such as uid:1000 or tcp:192.0.2.7
+    pub user: String,
+}
+
+impl Event {
+    //The event of `op` changing `allocation`
+    fn new(op: &str, allocation: &Allocation, time: u64, user: &str) -> Event {
+        Event {
+            time,
+            op: op.to_string(),
+            prefix: None,
+            mac: Some(allocation.mac),
+            owner: allocation.metadata.owner.clone(),
+            tags: allocation.metadata.tags.clone(),
+            expires: allocation.lease.map(|lease| lease.expires),
+            user: user.to_string(),
+        }
+    }
+
+    //The event as a JSON object.  Addresses are always written in the usual notation so the
+    //log reads the same whatever options the changes were made with.
+    pub fn to_json(&self) -> Json {
+        let tags = self.tags.iter()
+            .map(|(key, value)| (key.clone(), Json::from(value.as_str())))
+            .collect();
+
+        let mut fields = vec![
+            ("time", format_time(self.time).into()),
+            ("op", self.op.as_str().into()),
+        ];
+        match self.prefix {
+            Some(prefix) => fields.push(("prefix", prefix.to_string().into())),
+            None => {
+                fields.push(("mac", self.mac.map(|mac| mac.to_string()).into()));
+                fields.push(("owner", self.owner.as_deref().into()));
+                fields.push(("tags", Json::Object(tags)));
+                fields.push(("expires", self.expires.map(format_time).into()));
+            }
+        }
+        fields.push(("user", self.user.as_str().into()));
+
+        Json::object(fields)
+    }
+
+    //Reads an event written by to_json
+    pub fn from_json(value: &Json) -> Result<Event, String> {
+        let string = |key: &str| -> Result<Option<&str>, String> {
+            match value.get(key) {
+                None | Some(Json::Null) => Ok(None),
+                Some(Json::String(text)) => Ok(Some(text)),
+                Some(_) => Err(format!("{} is not a string", key)),
+            }
+        };
+        let time = |key: &str| -> Result<Option<u64>, String> {
+            match string(key)? {
+                Some(text) => parse_time(text).map(Some).ok_or_else(|| format!("{} is not a time", key)),
+                None => Ok(None),
+            }
+        };
+
+        let mut tags = BTreeMap::new();
+        match value.get("tags") {
+            None | Some(Json::Null) => {}
+            Some(Json::Object(fields)) => {
+                for (key, tag) in fields {
+                    let tag = tag.as_str().ok_or("tags are not all strings")?;
+                    tags.insert(key.clone(), tag.to_string());
+                }
+            }
+            Some(_) => return Err("tags is not an object".to_string()),
+        }
+
+        Ok(Event {
+            time: time("time")?.ok_or("the entry has no time")?,
+            op: string("op")?.ok_or("the entry has no op")?.to_string(),
+            prefix: match string("prefix")? {
+                Some(prefix) => Some(prefix.parse().map_err(|_| "prefix is not a prefix")?),
+                None => None,
+            },
+            mac: match string("mac")? {
+                Some(mac) => Some(mac.parse().map_err(|_| "mac is not a MAC address")?),
+                None => None,
+            },
+            owner: string("owner")?.map(|owner| owner.to_string()),
+            tags,
+            expires: time("expires")?,
+            user: string("user")?.unwrap_or("").to_string(),
+        })
+    }
+}
+
+//The changes made to a pool by one update, recorded as they are made
+pub struct Changes {
+    //The time every change in the update is made at
+    pub now: u64,
+    user: String,
+    events: Vec<Event>,
+}
+
+impl Changes {
+    //Records that `op` changed `allocation`
+    pub fn record(&mut self, op: &str, allocation: &Allocation) {
+        let event = Event::new(op, allocation, self.now, &self.user);
+        self.events.push(event);
+    }
+}
+
+//The audit log of the pool at `path`
+pub fn log_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".log");
+    PathBuf::from(name)
+}
+
+//Changes the pool at `path` like Pool::update, recording each change `user` makes in the
+//audit log.  The log is written before the pool is saved, while the pool's lock is held,
+//so the log lists changes in the order they were made and never misses one.  A save that
+//fails after the log was written is caught by verify.
+pub fn update<P, F, T>(path: P, user: &str, change: F) -> Result<T, PoolError>
+    where P: AsRef<Path>, F: FnOnce(&mut Pool, &mut Changes) -> Result<T, PoolError>
+{
+    let path = path.as_ref();
+    let _lock = Pool::lock(path)?;
+
+    let mut pool = Pool::open(path)?;
+    let mut changes = Changes {
+        now: now(),
+        user: user.to_string(),
+        events: Vec::new(),
+    };
+    let output = change(&mut pool, &mut changes)?;
+
+    append(path, &changes.events)?;
+    pool.save(path)?;
+    Ok(output)
+}
+
+//Records that `user` created the pool at `path`.  A pool created again at the same path
+//starts its history over from the new init.
+pub fn record_init(path: &Path, pool: &Pool, user: &str) -> Result<(), PoolError> {
+    let event = Event {
+        time: now(),
+        op: "init".to_string(),
+        prefix: Some(pool.prefix()),
+        mac: None,
+        owner: None,
+        tags: BTreeMap::new(),
+        expires: None,
+        user: user.to_string(),
+    };
+
+    let _lock = Pool::lock(path)?;
+    append(path, &[event])
+}
+
+//Appends events to the log in a single write and flushes them to disk
+fn append(path: &Path, events: &[Event]) -> Result<(), PoolError> {
+    if events.is_empty() {
+        return Ok(());
+    }
+
+    let mut lines = String::new();
+    for event in events {
+        lines.push_str(&format!("{}\n", event.to_json()));
+    }
+
+    let mut file = OpenOptions::new().append(true).create(true).open(log_path(path))?;
+    file.write_all(lines.as_bytes())?;
+    file.sync_data()?;
+    Ok(())
+}
+
+//Reads every entry of the log of the pool at `path`, with its line number.  A missing log
+//has no entries.  A line that cannot be read is returned as an error in its place.
+pub fn read(path: &Path) -> io::Result<Vec<(usize, Result<Event, String>)>> {
+    let file = match File::open(log_path(path)) {
+        Ok(file) => file,
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+
+    let mut entries = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let event = Json::parse(&line).and_then(|value| Event::from_json(&value));
+        entries.push((index + 1, event));
+    }
+
+    Ok(entries)
+}
+
+//Who is running pmg, by user ID so it cannot be changed by setting an environment variable
+#[cfg(unix)]
+pub fn local_user() -> String {
+    //SAFETY: getuid cannot fail and has no side effects
+    format!("uid:{}", unsafe { ::libc::getuid() })
+}
+
+#[cfg(not(unix))]
+pub fn local_user() -> String {
+    "local".to_string()
+}
+
+//Replays the log of the pool at `path` and compares it with the pool.  Returns a
+//description of every problem found, and the number of entries read.
+pub fn verify(path: &Path, pool: &Pool) -> Result<(Vec<String>, usize), PoolError> {
+    let entries = read(path)?;
+    let mut problems = Vec::new();
+
+    //Only the entries since the pool was last created describe it
+    let start = entries.iter()
+        .rposition(|(_, event)| event.as_ref().is_ok_and(|event| event.op == "init"));
+    match start {
+        Some(index) => {
+            if let (_, Ok(ref event)) = entries[index] {
+                if event.prefix != Some(pool.prefix()) {
+                    problems.push(format!("line {}: the log is for {}, not the pool's {}",
+                        entries[index].0, event.prefix.map_or("-".to_string(), |prefix| prefix.to_string()), pool.prefix()));
+                }
+            }
+        }
+        None => problems.push("the log has no init entry, so changes made before it began are unknown".to_string()),
+    }
+
+    let mut held: BTreeMap<MacAddress, Event> = BTreeMap::new();
+    for (line, event) in &entries[start.map_or(0, |index| index + 1)..] {
+        let event = match *event {
+            Ok(ref event) => event,
+            Err(ref error) => {
+                problems.push(format!("line {}: unreadable entry: {}", line, error));
+                continue;
+            }
+        };
+        let mac = match event.mac {
+            Some(mac) => mac,
+            None => {
+                problems.push(format!("line {}: {} entry has no mac", line, event.op));
+                continue;
+            }
+        };
+
+        match event.op.as_str() {
+            "alloc" => {
+                if held.insert(mac, event.clone()).is_some() {
+                    problems.push(format!("line {}: {} was allocated while already allocated", line, mac));
+                }
+            }
+            "renew" => {
+                if held.insert(mac, event.clone()).is_none() {
+                    problems.push(format!("line {}: {} was renewed while not allocated", line, mac));
+                }
+            }
+            "release" | "reap" => {
+                if held.remove(&mac).is_none() {
+                    problems.push(format!("line {}: {} was freed by {} while not allocated", line, mac, event.op));
+                }
+            }
+            op => problems.push(format!("line {}: unknown op '{}'", line, op)),
+        }
+    }
+
+    for allocation in pool.allocations() {
+        let mac = allocation.mac;
+        match held.remove(&mac) {
+            None => problems.push(format!("{} is allocated in the pool but not in the log", mac)),
+            Some(event) => {
+                if event.owner != allocation.metadata.owner || event.tags != allocation.metadata.tags {
+                    problems.push(format!("{} has a different owner or tags in the pool than in the log", mac));
+                }
+                if event.expires != allocation.lease.map(|lease| lease.expires) {
+                    problems.push(format!("{} has a different lease in the pool than in the log", mac));
+                }
+            }
+        }
+    }
+    for mac in held.keys() {
+        problems.push(format!("{} is allocated in the log but not in the pool", mac));
+    }
+
+    Ok((problems, entries.len()))
+}
@@ -0,0 +1,239 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use pmg::{MacAddress, Notation, ParseMacError};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::process;
+
+use cli::args::{self, select_notation, Args, Opt};
+use cli::json::Json;
+use cli::record::{self, Format, RecordWriter};
+
+//Exit status when a value typed in is not a MAC address
+const EXIT_INVALID: i32 = 1;
+
+//The options check takes, as well as args::NOTATION
+const OPTIONS: &[Opt] = &[
+    args::HELP,
+    Opt::value('F', "format"),
+];
+
+//The fields of a check record, as check_record writes them
+const CHECK_FIELDS: &[&str] = &[
+    "schema_version",
+    "input",
+    "valid",
+    "error",
+    "address",
+    "ul_bit",
+    "ig_bit",
+    "slap_quadrant",
+    "broadcast",
+    "all_zero",
+    "well_known",
+];
+
+const HELP: &str = "Help file for pmg check, which checks and classifies MAC addresses.
+
+SYNTAX
+    pmg check [<mac>...] [[-F] <string>] [[-s] <string>] [[-c] <string>] [[-g] <integer>] [-n]
+
+DESCRIPTION
+    Prints whether each value is a valid MAC address.  For a valid address it also
+    prints the address in the notation chosen by -s, -c, -g and -n, and:
+
+        Cast            unicast or multicast, from the I/G bit
+        Administration  universally or locally administered, from the U/L bit
+        SLAP Quadrant   the IEEE 802c quadrant of a locally administered address:
+                        AAI (x2), ELI (xA), SAI (xE) or Reserved (x6)
+        Broadcast       whether it is ff:ff:ff:ff:ff:ff
+        All Zero        whether it is 00:00:00:00:00:00
+        Well Known      the protocol the address is set aside for, such as LLDP,
+                        VRRP or IPv4 multicast, or no
+
+    Colon, hyphen, Cisco dotted and bare hexadecimal notations are accepted, in upper,
+    lower or mixed case, and colon or hyphen octets may leave out a leading zero, as
+    in 2:5a:10:0:0:1.  Reads one value per line from standard input when no values
+    are typed in.  Blank lines and lines starting with # are skipped.
+
+OPTIONS
+    -F  --format        How the reports are written.
+                        Accepted Values:  text json csv yaml tsv
+                        Defaults:  text
+                        Notes:  Every format but text prints one record per value.  See
+                                RECORDS.
+
+    -s, -c, -g and -n change how the addresses are written, as in 'pmg gen -h'.
+
+RECORDS
+    -F/--format json, csv, yaml or tsv prints one record per value, with these fields
+    in this order.  The fields after error are null when the value is not valid.
+
+    schema_version  1.  Fields are only added after well_known.  Any other change to
+                    the fields gives a new version.
+    input           The value as it was typed in.
+    valid           true or false.
+    error           Why the value is not a MAC address, or null.
+    address         The address in the notation chosen by -s, -c, -g and -n.
+    ul_bit          1 when locally administered, 0 when universally administered.
+    ig_bit          1 when multicast, 0 when unicast.
+    slap_quadrant   AAI, ELI, SAI or Reserved, or null when universally administered.
+    broadcast       true or false.
+    all_zero        true or false.
+    well_known      The protocol the address is set aside for, or null.
+
+EXIT STATUS
+    0 when every value is a valid MAC address, 1 when any is not and 2 when an option
+    could not be used.
+
+EXAMPLES
+    pmg check 02:5a:10:00:00:01
+    pmg check 025A.1000.0001 02-5a-10-00-00-01 025a10000001
+    pmg check 01:80:c2:00:00:0e
+    pmg check \"$MAC\" > /dev/null || exit 1
+    cut -d' ' -f1 /etc/ethers | pmg check";
+
+//Runs the check subcommand
+pub fn run(args: &[String]) {
+    let parsed_args = Args::parse("pmg check", &[OPTIONS, args::NOTATION].concat(), args);
+
+    if parsed_args.flag("help") {
+        println!("{}", HELP);
+        return;
+    }
+
+    let format = record::select_format(&parsed_args, "text");
+    let notation = select_notation(&parsed_args);
+    let result = if parsed_args.positionals.is_empty() {
+        let stdin = io::stdin();
+        let lines = stdin.lock().lines();
+        check_all(lines, &notation, format)
+    } else {
+        check_all(parsed_args.positionals.iter().cloned().map(Ok), &notation, format)
+    };
+
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(EXIT_INVALID),
+        result => args::finish(result.map(|_| ())),
+    }
+}
+
+//Checks every value as it is read, so a long list is never held in memory.  Returns whether
+//every value was an address.
+fn check_all<I: Iterator<Item = io::Result<String>>>(values: I, notation: &Notation, format: Format) -> io::Result<bool> {
+    let mut all_valid = true;
+
+    let checked = values.filter_map(|value| {
+        let value = match value {
+            Ok(value) => value,
+            Err(error) => return Some(Err(error)),
+        };
+
+        //Blank lines and comments are skipped so address lists can be annotated
+        let value = value.trim();
+        if value.is_empty() || value.starts_with('#') {
+            return None;
+        }
+
+        let mac = value.parse::<MacAddress>();
+        all_valid &= mac.is_ok();
+        Some(Ok((value.to_string(), mac)))
+    });
+    print_all(checked, notation, format)?;
+
+    Ok(all_valid)
+}
+
+//Prints a labelled report for every value, or a record
+fn print_all<I>(checked: I, notation: &Notation, format: Format) -> io::Result<()>
+    where I: Iterator<Item = io::Result<(String, Result<MacAddress, ParseMacError>)>>
+{
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+
+    if format != Format::Text {
+        let mut writer = RecordWriter::new(&mut out, format, CHECK_FIELDS)?;
+        for checked in checked {
+            let (value, mac) = checked?;
+            writer.write(&check_record(&value, &mac, notation))?;
+        }
+        writer.finish()?;
+        return out.flush();
+    }
+
+    for checked in checked {
+        let (value, mac) = checked?;
+        writeln!(out, "Input:                 {}", value)?;
+        match mac {
+            Ok(mac) => {
+                writeln!(out, "Address:               {}", notation.address(&mac))?;
+                writeln!(out, "Valid:                 yes")?;
+                report(&mut out, &mac)?;
+            }
+            Err(error) => writeln!(out, "Valid:                 no, {}", error)?,
+        }
+        //Each report is followed by a blank line, as the labelled output of gen is
+        writeln!(out)?;
+    }
+
+    out.flush()
+}
+
+//The record of one value.  The fields describing the address are null when it is not valid.
+fn check_record(value: &str, mac: &Result<MacAddress, ParseMacError>, notation: &Notation) -> Json {
+    let error = mac.as_ref().err().map(|error| error.to_string());
+    let mac = mac.as_ref().ok();
+
+    Json::object(vec![
+        ("schema_version", record::SCHEMA_VERSION.into()),
+        ("input", value.into()),
+        ("valid", mac.is_some().into()),
+        ("error", error.into()),
+        ("address", mac.map(|mac| notation.address(mac)).into()),
+        ("ul_bit", mac.map(|mac| u64::from(mac.is_local())).into()),
+        ("ig_bit", mac.map(|mac| u64::from(mac.is_multicast())).into()),
+        ("slap_quadrant", mac.and_then(MacAddress::slap_quadrant).map(|quadrant| quadrant.abbreviation()).into()),
+        ("broadcast", mac.map(MacAddress::is_broadcast).into()),
+        ("all_zero", mac.map(MacAddress::is_zero).into()),
+        ("well_known", mac.and_then(MacAddress::well_known).into()),
+    ])
+}
+
+//Prints what kind of address a valid MAC address is
+fn report<W: Write>(out: &mut W, mac: &MacAddress) -> io::Result<()> {
+    let cast = if mac.is_multicast() { "multicast, I/G bit set" } else { "unicast, I/G bit clear" };
+    let administration = if mac.is_local() {
+        "locally administered, U/L bit set"
+    } else {
+        "universally administered, U/L bit clear"
+    };
+    writeln!(out, "Cast:                  {}", cast)?;
+    writeln!(out, "Administration:        {}", administration)?;
+
+    //Only locally administered addresses fall in a quadrant
+    match mac.slap_quadrant() {
+        Some(quadrant) => writeln!(out, "SLAP Quadrant:         {}, {} (x{})",
+            quadrant.abbreviation(), quadrant.name(), quadrant.digit())?,
+        None => writeln!(out, "SLAP Quadrant:         none, universally administered")?,
+    }
+
+    writeln!(out, "Broadcast:             {}", yes_no(mac.is_broadcast()))?;
+    writeln!(out, "All Zero:              {}", yes_no(mac.is_zero()))?;
+    writeln!(out, "Well Known:            {}", mac.well_known().unwrap_or("no"))
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value { "yes" } else { "no" }
+}
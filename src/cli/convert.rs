@@ -0,0 +1,164 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use pmg::{MacAddress, Notation, Template};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::process;
+
+use cli::args::{self, select_notation, Args, Opt};
+use cli::gen::select_template;
+use cli::record::{self, Format, RecordWriter};
+
+//The options convert takes, as well as args::NOTATION
+const OPTIONS: &[Opt] = &[
+    args::HELP,
+    Opt::value('F', "format"),
+    Opt::value('t', "template"),
+];
+
+const HELP: &str = "Help file for pmg convert, which rewrites MAC addresses in another notation.
+
+SYNTAX
+    pmg convert [<mac>...] [[-F] <string> | [-t] <string>] [[-s] <string>] [[-c] <string>] [[-g] <integer>] [-n]
+
+DESCRIPTION
+    Prints each address in the notation chosen by -s, -c, -g and -n, one per line and
+    in the order they were read.  Colon, hyphen, Cisco dotted and bare hexadecimal
+    notations are accepted, in upper, lower or mixed case.  Reads one address per line
+    from standard input when no addresses are typed in.  Blank lines and lines
+    starting with # are skipped.
+
+    A value that is not a MAC address is reported on stderr and skipped, and pmg exits
+    with 1 once the rest have been converted.
+
+OPTIONS
+    -F  --format        How the output is written.
+                        Accepted Values:  text json csv yaml tsv
+                        Defaults:  text
+                        Notes:  Every format but text prints one record per address,
+                                as described in 'pmg gen -h'.
+    -t  --template      Prints each address through a template, as described in
+                        'pmg gen -h'.  {index} counts the addresses converted.
+
+    -s, -c, -g and -n change how the addresses are written, as in 'pmg gen -h'.
+
+EXIT STATUS
+    0 when every value was converted, 1 when any was not a MAC address and 2 when an
+    option could not be used.
+
+EXAMPLES
+    pmg convert 025a.1000.0001 -s - -c u
+    pmg convert 02:5a:10:00:00:01 -s .
+    pmg convert 02-5A-10-00-00-01 -t '{int}'
+    cut -d' ' -f1 /etc/ethers | pmg convert -n";
+
+//How the addresses are written out, chosen by the notation arguments, -F/--format and
+//-t/--template
+struct Output {
+    notation: Notation,
+    format: Format,
+    template: Option<Template>,
+}
+
+//Runs the convert subcommand
+pub fn run(args: &[String]) {
+    let parsed_args = Args::parse("pmg convert", &[OPTIONS, args::NOTATION].concat(), args);
+
+    if parsed_args.flag("help") {
+        println!("{}", HELP);
+        return;
+    }
+
+    let format = record::select_format(&parsed_args, "text");
+    let output = Output {
+        notation: select_notation(&parsed_args),
+        format,
+        template: select_template(&parsed_args, format),
+    };
+
+    let result = if parsed_args.positionals.is_empty() {
+        let stdin = io::stdin();
+        let lines = stdin.lock().lines();
+        convert_all(lines, &output)
+    } else {
+        convert_all(parsed_args.positionals.iter().cloned().map(Ok), &output)
+    };
+
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        result => args::finish(result.map(|_| ())),
+    }
+}
+
+//Converts every value as it is read, so a long list is never held in memory.  Returns whether
+//every value was an address.
+fn convert_all<I: Iterator<Item = io::Result<String>>>(values: I, output: &Output) -> io::Result<bool> {
+    let mut all_valid = true;
+
+    let macs = values.filter_map(|value| {
+        let value = match value {
+            Ok(value) => value,
+            Err(error) => return Some(Err(error)),
+        };
+
+        //Blank lines and comments are skipped so address lists can be annotated
+        let value = value.trim();
+        if value.is_empty() || value.starts_with('#') {
+            return None;
+        }
+
+        match value.parse::<MacAddress>() {
+            Ok(mac) => Some(Ok(mac)),
+            Err(error) => {
+                eprintln!("pmg: invalid MAC address '{}': {}", value, error);
+                all_valid = false;
+                None
+            }
+        }
+    });
+    print_all(macs, output)?;
+
+    Ok(all_valid)
+}
+
+//Prints each address on its own line, or as records
+fn print_all<I: Iterator<Item = io::Result<MacAddress>>>(macs: I, output: &Output) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+
+    if output.format != Format::Text {
+        let mut writer = RecordWriter::new(&mut out, output.format, record::ADDRESS_FIELDS)?;
+        for mac in macs {
+            writer.write(&record::address_record(&mac?, None, &output.notation))?;
+        }
+        writer.finish()?;
+        return out.flush();
+    }
+
+    //Each line is formatted into the same buffer rather than allocating a string per address
+    let mut line = String::with_capacity(32);
+    for (index, mac) in macs.enumerate() {
+        let mac = mac?;
+        line.clear();
+        match output.template {
+            Some(ref template) => template.write(&mut line, &output.notation, &mac, None, index as u64),
+            None => output.notation.write_octets(&mac.octets(), &mut line),
+        }
+        line.push('\n');
+        out.write_all(line.as_bytes())?;
+    }
+
+    out.flush()
+}
@@ -0,0 +1,199 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use pmg::{Allocation, MacAddress, Notation, Pool};
+use std::io::{self, BufWriter, Write};
+use std::process;
+
+use cli::args::{self, Args, Opt};
+use cli::json::Json;
+use cli::record::{self, Format, RecordWriter};
+
+//The options emit takes
+const OPTIONS: &[Opt] = &[
+    args::HELP,
+    Opt::value('F', "format"),
+];
+
+//The fields of an emit record, as emit_record writes them
+const EMIT_FIELDS: &[&str] = &["schema_version", "target", "mac", "host_name", "owner"];
+
+const HELP: &str = "Help file for pmg emit, which writes the allocations of a pool as configuration.
+
+SYNTAX
+    pmg emit <target> <file> [[-F] <string>]
+
+DESCRIPTION
+    Writes every address allocated from a pool file, in address order, in a form another
+    program reads, so hosts keep the address the pool gave them.  The output starts with
+    a comment naming the pool.
+
+TARGETS
+    ethers          /etc/ethers lines, the address and then the host name.
+    dnsmasq         dnsmasq dhcp-host=<address>,<host name> lines.
+    dhcpd           ISC dhcpd host declarations, each with the address as its hardware
+                    ethernet and the host name as its host-name option.  Declarations
+                    are named after the address, so an owner with several addresses
+                    does not repeat a name.
+
+HOST NAMES
+    The owner of an allocation is its host name.  An allocation without an owner, or
+    whose owner is not a host name of letters, digits, hyphens and dots, is named pmg-
+    followed by its address without separators, such as pmg-025a10000001.
+
+OPTIONS
+    -F  --format        How the output is written.
+                        Accepted Values:  config json csv yaml tsv
+                        Defaults:  config
+                        Notes:  Every format but config prints one record per
+                                allocation instead of the configuration, with
+                                schema_version, target, mac, host_name and owner
+                                fields.  schema_version is 1, and fields are only
+                                added after owner.  owner is null when the allocation
+                                has none.
+
+EXIT STATUS
+    0 on success, 1 when the pool could not be read and 2 when a value could not be
+    used.
+
+EXAMPLES
+    pmg emit ethers lab.pool >> /etc/ethers
+    pmg emit dnsmasq lab.pool > /etc/dnsmasq.d/lab.conf
+    pmg emit dhcpd lab.pool > /etc/dhcp/lab-hosts.conf
+    pmg emit ethers lab.pool -F json";
+
+//The programs emit writes configuration for
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Target {
+    Ethers,
+    Dnsmasq,
+    Dhcpd,
+}
+
+impl Target {
+    //The name the target is typed in as
+    fn name(&self) -> &'static str {
+        match *self {
+            Target::Ethers => "ethers",
+            Target::Dnsmasq => "dnsmasq",
+            Target::Dhcpd => "dhcpd",
+        }
+    }
+}
+
+//Runs the emit subcommand
+pub fn run(args: &[String]) {
+    let parsed_args = Args::parse("pmg emit", OPTIONS, args);
+
+    if parsed_args.flag("help") || parsed_args.positionals.is_empty() {
+        println!("{}", HELP);
+        return;
+    }
+
+    let (target, path) = match parsed_args.positionals.as_slice() {
+        [target, path] => (target.as_str(), path.as_str()),
+        _ => parsed_args.usage("emit takes a target and a pool file"),
+    };
+    let target = match target {
+        "ethers" => Target::Ethers,
+        "dnsmasq" => Target::Dnsmasq,
+        "dhcpd" => Target::Dhcpd,
+        target => parsed_args.usage(&format!("unknown target '{}': expected ethers, dnsmasq or dhcpd", target)),
+    };
+    let format = record::select_format(&parsed_args, "config");
+
+    let pool = match Pool::open(path) {
+        Ok(pool) => pool,
+        Err(error) => {
+            eprintln!("pmg: {}: {}", path, error);
+            process::exit(1);
+        }
+    };
+
+    let result = if format == Format::Text {
+        emit(&pool, path, target)
+    } else {
+        emit_records(&pool, target, format)
+    };
+    args::finish(result);
+}
+
+//Writes every allocation of the pool for `target`
+fn emit(pool: &Pool, path: &str, target: Target) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    //Every target reads addresses in lower case colon notation
+    let notation = Notation::default();
+
+    writeln!(out, "# Written by pmg emit from {} ({})", path, pool.prefix())?;
+    for allocation in pool.allocations() {
+        let mac = notation.address(&allocation.mac);
+        let name = host_name(allocation);
+
+        match target {
+            Target::Ethers => writeln!(out, "{} {}", mac, name)?,
+            Target::Dnsmasq => writeln!(out, "dhcp-host={},{}", mac, name)?,
+            Target::Dhcpd => {
+                writeln!(out, "host pmg-{} {{", bare(&allocation.mac))?;
+                writeln!(out, "  hardware ethernet {};", mac)?;
+                writeln!(out, "  option host-name \"{}\";", name)?;
+                writeln!(out, "}}")?;
+            }
+        }
+    }
+
+    out.flush()
+}
+
+//Prints every allocation of the pool as a record, with the host name `target` would be given
+fn emit_records(pool: &Pool, target: Target, format: Format) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let notation = Notation::default();
+
+    let mut writer = RecordWriter::new(&mut out, format, EMIT_FIELDS)?;
+    for allocation in pool.allocations() {
+        writer.write(&Json::object(vec![
+            ("schema_version", record::SCHEMA_VERSION.into()),
+            ("target", target.name().into()),
+            ("mac", notation.address(&allocation.mac).into()),
+            ("host_name", host_name(allocation).into()),
+            ("owner", allocation.metadata.owner.clone().into()),
+        ]))?;
+    }
+    writer.finish()?;
+
+    out.flush()
+}
+
+//The owner of the allocation if it can be used as a host name, otherwise a name made from the
+//address
+fn host_name(allocation: &Allocation) -> String {
+    let valid = |owner: &&String| {
+        !owner.is_empty()
+            && owner.len() <= 253
+            && !owner.starts_with(['-', '.'])
+            && owner.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+    };
+
+    match allocation.metadata.owner.as_ref().filter(valid) {
+        Some(owner) => owner.clone(),
+        None => format!("pmg-{}", bare(&allocation.mac)),
+    }
+}
+
+//The address as lower case hexadecimal without separators, for names
+fn bare(mac: &MacAddress) -> String {
+    Notation { separator: None, ..Notation::default() }.address(mac)
+}
@@ -0,0 +1,534 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use pmg::{EntropySource, GenerateError, Generator, MacAddress, MacPrefix, Notation, Template};
+use std::io::{self, BufWriter, Write};
+use std::process;
+
+use cli::args::{self, select_notation, Args, Opt};
+use cli::record::{self, Format, RecordWriter};
+
+//The value generated by the library for printing
+enum Generated {
+    //A single MAC address when -u/--unique is used
+    Address(MacAddress),
+    //A MAC prefix and its assignable range
+    Prefix(MacPrefix),
+}
+
+struct MachineAddress {
+    //The MAC addresses or prefixes that will be printed
+    macs: Vec<Generated>,
+    //Determines if a help message should print when no args are passed.
+    arg_count: usize,
+    //Determines the separator and capitalization for hexadecimal output
+    notation: Notation,
+    //Prints one address or prefix per line without any labels when true
+    plain: bool,
+    //The fixed prefix the addresses were drawn from, if one was given
+    within: Option<MacPrefix>,
+    //Prints records for other programs instead of text when not Format::Text
+    format: Format,
+    //Prints each address or prefix through the user's template instead of the fixed text
+    template: Option<Template>,
+}
+
+impl MachineAddress {
+    //Prints a single address or prefix with labels describing what was generated
+    fn print_labeled(&self, out: &mut dyn Write, mac: &Generated) -> io::Result<()> {
+        match *mac {
+            Generated::Address(ref mac) => {
+                writeln!(out, "Private MAC Address:   {}", self.notation.address(mac))?;
+            }
+            //We only need to print the assignable range if we didn't print a unique address
+            Generated::Prefix(ref prefix) => {
+                writeln!(out, "Private MAC Prefix:    {}", self.format_prefix(prefix))?;
+                //There are 2 addresses per assignable bit, the prefix knows how many bits were not
+                //randomly generated.  The count is exact for every prefix length from 8 to 47 bits.
+                writeln!(out, "Assignable Addresses:  {}", prefix.count())?;
+                //The next lines displays the assignable range the has been generated.
+                writeln!(out, "Assigned Addresses:    {} - {}",
+                    self.notation.address(&prefix.first()),
+                    self.notation.address(&prefix.last()))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    //Prints a single address or prefix on its own line for use in scripts
+    fn print_plain(&self, out: &mut dyn Write, mac: &Generated) -> io::Result<()> {
+        match *mac {
+            Generated::Address(ref mac) => writeln!(out, "{}", self.notation.address(mac)),
+            Generated::Prefix(ref prefix) => writeln!(out, "{}", self.format_prefix(prefix)),
+        }
+    }
+
+    //A prefix that ends on an octet boundary is printed as its octets.  Any other prefix is
+    //printed as its first address and length, since its last octet is only partly fixed.
+    fn format_prefix(&self, prefix: &MacPrefix) -> String {
+        if prefix.is_octet_aligned() {
+            self.notation.prefix(prefix)
+        } else {
+            format!("{}/{}", self.notation.address(&prefix.first()), prefix.prefix_len())
+        }
+    }
+
+    //This command prints out every generated address or prefix in a format to display to user
+    fn print(&self) -> io::Result<()> {
+        //Large batches are written through a buffer rather than a line at a time
+        let stdout = io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+
+        if self.format != Format::Text {
+            self.print_records(&mut out)?;
+            return out.flush();
+        }
+        if let Some(ref template) = self.template {
+            self.print_template(&mut out, template)?;
+            return out.flush();
+        }
+
+        //Print a simple message if ran without any arguments in case the user doesn't know how
+        //to access the help file.
+        if self.arg_count == 0 {
+            writeln!(out, "No arguments were used.  Type pmg -h or pmg --help for more information.")?;
+            writeln!(out, "Generating MAC addresses for default settings: -r 1 -s : -c l")?;
+            writeln!(out)?;
+        }
+
+        for mac in &self.macs {
+            if self.plain {
+                self.print_plain(&mut out, mac)?;
+            } else {
+                //Each labeled address or prefix is followed by a blank line
+                self.print_labeled(&mut out, mac)?;
+                writeln!(out)?;
+            }
+        }
+
+        out.flush()
+    }
+
+    //Prints every address or prefix through a template, one per line.  A prefix fills in the
+    //address fields with its first address.
+    fn print_template(&self, out: &mut dyn Write, template: &Template) -> io::Result<()> {
+        let mut line = String::new();
+        for (index, mac) in self.macs.iter().enumerate() {
+            line.clear();
+            match *mac {
+                Generated::Address(ref mac) => template.write(&mut line, &self.notation, mac, self.within.as_ref(), index as u64),
+                Generated::Prefix(ref prefix) => template.write(&mut line, &self.notation, &prefix.first(), Some(prefix), index as u64),
+            }
+            line.push('\n');
+            out.write_all(line.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    //Prints every address or prefix as a record, see cli::record
+    fn print_records(&self, out: &mut dyn Write) -> io::Result<()> {
+        let mut writer = RecordWriter::new(out, self.format, record::ADDRESS_FIELDS)?;
+        for mac in &self.macs {
+            let record = match *mac {
+                Generated::Address(ref mac) => record::address_record(mac, self.within.as_ref(), &self.notation),
+                Generated::Prefix(ref prefix) => record::prefix_record(prefix, &self.notation),
+            };
+            writer.write(&record)?;
+        }
+
+        writer.finish()
+    }
+}
+
+//The options taken when generating addresses, as well as args::NOTATION
+const OPTIONS: &[Opt] = &[
+    args::HELP,
+    Opt::flag('u', "unique"),
+    Opt::flag('p', "plain"),
+    Opt::flag('v', "verbose"),
+    Opt::flag('f', "force"),
+    Opt::value('P', "prefix"),
+    Opt::value('r', "range"),
+    Opt::value('l', "length"),
+    Opt::value('N', "count"),
+    Opt::value('F', "format"),
+    Opt::value('t', "template"),
+    Opt::value('S', "seed"),
+    Opt::value('R', "rng"),
+    Opt::value('E', "entropy-file"),
+];
+
+const HELP: &str = "Help file for pmg gen, random locally administered MAC addresses and prefixes.
+
+SYNTAX POSIX
+    pmg gen [-h] [-u] [-n] [-p] [-v] [-f] [[-P] <prefix>] [[-r] <integer> | [-l] <integer>] [[-s] <string>] [[-c] <string>] [[-g] <integer>] [[-N] <integer>] [[-F] <string> | [-t] <string>] [[-S] <integer> | [-R] <string> | [-E] <path>]
+
+SYNTAX GNU
+    pmg gen [--help] [--unique] [--noSeparator] [--plain] [--verbose] [--force] [[--prefix] <prefix>] [[--range] <integer> | [--length] <integer>] [[--separator] <string>] [[--case] <string>] [[--group-size] <integer>] [[--count] <integer>] [[--format] <string> | [--template] <string>] [[--seed] <integer> | [--rng] <string> | [--entropy-file] <path>]
+
+DESCRIPTION
+    Prints random locally administered unicast MAC prefixes, or addresses with -u/--unique.
+    pmg without a command is the same as pmg gen, so pmg -u is pmg gen -u.
+
+USAGE
+    POSIX   GNU             NOTES
+    -h      --help          Displays help message.
+                            Overrides:  All
+
+    -u      --unique        Generates a single MAC address.
+                            Overrides: -r/--range.
+                            Notes:  Cannot be used with -l/--length.
+    -n      --noSeparator   Generates a MAC address or prefix without a separator.
+                            Overrides: -s/--separator
+
+    -P      --prefix        A fixed prefix.  Only the bits after it are randomized.
+                            Accepted Values:  1 to 5 octets, such as 02:5a:10, or an address
+                                              and length, such as 02:5a:10:40:00:00/28
+                            Notes:  The prefix must be locally administered unicast,
+                                    its second digit must be 2, 6, a or e.
+
+    -f      --force         Uses a -P/--prefix that is not locally administered
+                            unicast.  A warning is printed instead of an error.
+
+    -r      --range         Generates a MAC prefix for a range of private addresses.
+                            Accepted Values:  1 2 3
+                            Defaults: 1
+                            Notes:  Refers to how many octets to use to generate your
+                                    private MAC prefix.
+                                    1 (1 octet)  =      256 assignable addresses
+                                    2 (2 octets) =    65536 assignable addresses
+                                    3 (3 octets) = 16777216 assignable addresses
+
+    -l      --length        Generates a MAC prefix of this many bits.
+                            Accepted Values:  8 to 47
+                            Overrides: -r/--range.
+                            Notes:  A prefix of n bits has 2^(48 - n) assignable addresses.
+
+    -s      --separator     The separator used for the MAC address.
+                            Accepted Values:  : - .
+                            Defaults:  :
+                            Notes:  . prints Cisco notation, xxxx.xxxx.xxxx.
+
+    -g      --group-size    The number of octets printed together between separators.
+                            Accepted Values:  1 2 3
+                            Defaults:  1, or 2 when the separator is .
+
+    -c      --case          The case the hexadecimal letters are shown in.
+                            Accepted Values:  l u lower upper
+                            Defaults:  l
+
+    -N      --count         The number of distinct addresses or prefixes to generate.
+                            Accepted Values:  Any whole number from 1
+                            Defaults:  1
+
+    -p      --plain         Prints one address or prefix per line without labels.
+
+    -F      --format        Prints records for other programs instead of text.
+                            Accepted Values:  text json csv yaml tsv
+                            Defaults:  text
+                            Notes:  See OUTPUT FORMATS.
+
+    -t      --template      Prints each address or prefix on its own line through a template.
+                            Accepted Values:  Text with placeholders, such as '{mac} {index}'
+                            Notes:  See TEMPLATES.
+
+    -S      --seed          Seeds the generator so the same output is produced on every run.
+                            Accepted Values:  Any whole number up to 18446744073709551615
+                            Notes:  A seed gives the same output on every machine and every
+                                    release of pmg.  Seeded output is predictable and
+                                    should only be used for tests and fixtures.
+
+    -R      --rng           The random number generator to draw from.
+                            Accepted Values:  thread os
+                            Defaults:  thread
+                            Notes:  os uses the operating system's cryptographically
+                                    secure generator.
+
+    -E      --entropy-file  Reads raw random bytes from a file instead of a generator.
+                            Accepted Values:  A path, or - for standard input
+                            Notes:  Every 4 bytes are used as one 32 bit value.  pmg stops
+                                    with an error if the file runs out of bytes.
+
+    -v      --verbose       Prints details such as the entropy source to stderr.
+
+EXAMPLES
+    pmg -u                  Provides a single MAC address of:  xx:xx:xx:xx:xx:xx
+    pmg -r 2 -c u -s :      Provides a MAC prefix of:  XX:XX:XX:XX
+    pmg -s -                Provides a MAC prefix of:  xx-xx-xx-xx-xx
+    pmg -P 02:5a:10 -u      Provides a single MAC address of:  02:5a:10:xx:xx:xx
+    pmg -l 28               Provides a MAC prefix of:  xx:xx:xx:x0:00:00/28
+    pmg -P 02:5a:10:40:00:00/28 -u
+                            Provides a single MAC address of:  02:5a:10:4x:xx:xx
+    pmg -u -s .             Provides a single MAC address in Cisco notation: xxxx.xxxx.xxxx
+    pmg -u -N 5000 -p       Provides 5000 distinct MAC addresses, one per line
+    pmg -S 1234 -u -N 10    Provides the same 10 MAC addresses on every run
+    pmg -u -N 10 -F csv     Provides 10 MAC addresses as CSV records
+    pmg -u -N 3 -t 'host-{index:1} {mac:upper:none}'
+                            Provides host-1 XXXXXXXXXXXX to host-3 XXXXXXXXXXXX
+    pmg -R os -u            Provides a single MAC address from the OS generator
+
+OUTPUT FORMATS
+    -F/--format json, csv, yaml or tsv prints one record per address or prefix, with
+    these fields in this order.  range and prefix print the same records.
+
+    schema_version  1.  Fields are only added after ig_bit.  Any other change to the
+                    fields gives a new version.  The same rule holds for every other
+                    record pmg prints, such as pool allocations, each of which also
+                    starts with schema_version.
+    kind            address or prefix.
+    address         The address, or null for a prefix.
+    prefix          The prefix as its first address and length, or null for an
+                    address not drawn from a -P/--prefix.
+    prefix_length   The length of the prefix in bits.
+    first, last     The first and last address of the prefix.
+    count           The number of addresses in the prefix.
+    ul_bit          1 when locally administered, 0 when universally administered.
+    ig_bit          1 when multicast, 0 when unicast.
+
+    json prints an array of objects and yaml a sequence of mappings, with null for
+    missing values.  csv and tsv print a heading row and leave missing values empty.
+    Addresses are written in the notation chosen by -s, -c, -g and -n.
+
+TEMPLATES
+    -t/--template prints each address or prefix through a template, such as
+    'host-{index:1} {mac:upper:-}'.  A placeholder is a field in braces followed by any
+    modifiers, each after a colon.  Write {{ and }} for literal braces.  A prefix fills
+    in the address fields with its first address.
+
+    mac             The address.
+    eui64           The address as an EUI-64, with ff:fe inserted in the middle.
+    oui, nic        The first and last three octets of the address.
+    octet1..octet6  A single octet of the address.
+    int             The address as a decimal integer.
+    index           The position in the batch, from 0.  {index:1} counts from 1.
+    prefix          The -P/--prefix or generated prefix, as its first address and length.
+    first, last     The first and last address of the prefix.
+    length, count   The length of the prefix in bits and its number of addresses.
+
+    The prefix fields are empty for an address not drawn from a -P/--prefix.  Fields
+    written in hexadecimal take these modifiers, and otherwise follow -s, -c, -g and -n:
+
+    upper, lower    The case of the letters.
+    - . _ colon     The separator.  Any single character other than a letter or digit.
+    none            No separator.
+    g1..g8          The number of octets written together between separators.
+    modified        eui64 only.  Flips the U/L bit, as IPv6 interface identifiers do.
+
+EXIT STATUS
+    0 on success, 1 when the addresses could not be generated, such as when the entropy
+    file runs out, and 2 when an option or value could not be used, such as a -N/--count
+    larger than the prefix holds or a -l/--length no longer than the prefix.";
+
+//Runs the gen subcommand, which generates and prints MAC addresses or prefixes.  pmg without a
+//command runs it too.
+pub fn run(args: &[String]) {
+    //Parse the arguments into usable struct.  Anything that cannot be used stops here.
+    let parsed_args = Args::parse("pmg gen", &[OPTIONS, args::NOTATION].concat(), args);
+
+    //Print help menu if argument was used, otherwise print the MAC address
+    if parsed_args.flag("help") {
+        println!("{}", HELP);
+        return;
+    }
+
+    //Every value is typed in after its option, so anything else is a mistyped subcommand
+    if let Some(value) = parsed_args.positionals.first() {
+        parsed_args.usage(&format!("unknown command '{}'", value));
+    }
+
+    //This option determines how many octets will needed to be generated.
+    let octet_range = parsed_args.choice("range", &[("1", 1), ("2", 2), ("3", 3)]).unwrap_or(1);
+
+    //Like octet_range, the unique argument must be parsed before generating to determine
+    //how many octets to generate.
+    let unique = parsed_args.flag("unique");
+
+    //How many distinct addresses or prefixes to generate in one run
+    let count = match parsed_args.parse_value::<usize>("count", "a whole number from 1") {
+        Some(0) => parsed_args.invalid("count", "0", "a whole number from 1"),
+        Some(count) => count,
+        None => 1,
+    };
+
+    //The length of the generated prefix in bits.  -l/--length allows prefixes that do not
+    //end on an octet boundary and overrides -r/--range.
+    let prefix_len = match parsed_args.value("length") {
+        Some(value) => match value.parse::<u8>() {
+            Ok(len @ 8..=47) => len,
+            _ => parsed_args.invalid("length", value, "a whole number from 8 to 47"),
+        },
+        None => ((6 - octet_range) * 8) as u8,
+    };
+
+    //An address has no prefix length, so a length given with -u/--unique would be ignored
+    if unique && parsed_args.value("length").is_some() {
+        parsed_args.usage("-l/--length cannot be used with -u/--unique");
+    }
+
+    //A fixed prefix supplied by the user.  Only the bits after it are randomized.
+    let fixed_prefix = select_prefix(&parsed_args);
+
+    //Read before generating so a mistyped format does not waste a large batch
+    let format = record::select_format(&parsed_args, "text");
+    let template = select_template(&parsed_args, format);
+    let notation = select_notation(&parsed_args);
+
+    //Work out where the random bits come from and open that source
+    let source = select_entropy(&parsed_args);
+    let entropy = match source.open() {
+        Ok(entropy) => entropy,
+        Err(error) => {
+            eprintln!("pmg: unable to open entropy source {}: {}", source, error);
+            process::exit(1);
+        }
+    };
+
+    //Verbose details go to stderr so they never mix with the generated addresses
+    if parsed_args.flag("verbose") {
+        eprintln!("Entropy Source:        {}", source);
+    }
+
+    let mut generator = Generator::new(entropy);
+
+    //Generate the MAC addresses based on the arguments that were parsed
+    let generated = match (unique, fixed_prefix) {
+        (true, None) => generator.addresses(count)
+            .map(|macs| macs.into_iter().map(Generated::Address).collect()),
+        (true, Some(ref prefix)) => generator.addresses_within(prefix, count)
+            .map(|macs| macs.into_iter().map(Generated::Address).collect()),
+        (false, None) => generator.prefixes_of_len(prefix_len, count)
+            .map(|prefixes| prefixes.into_iter().map(Generated::Prefix).collect()),
+        (false, Some(ref prefix)) => generator.prefixes_within(prefix, prefix_len, count)
+            .map(|prefixes| prefixes.into_iter().map(Generated::Prefix).collect()),
+    };
+
+    //A count larger than the addresses that can be generated, or a length that leaves no room
+    //inside the -P/--prefix, is refused before anything is drawn, as any other value that
+    //cannot be used
+    let macs = match generated {
+        Ok(macs) => macs,
+        Err(GenerateError::Exhausted { requested, available }) => parsed_args.usage(&format!(
+            "invalid -N/--count '{}': only {} distinct values can be generated", requested, available)),
+        Err(error @ GenerateError::PrefixTooLong { .. }) => parsed_args.usage(&format!(
+            "{}\nUse a -l/--length or -r/--range longer than the -P/--prefix.", error)),
+        Err(error) => {
+            eprintln!("pmg: {}", error);
+            process::exit(1);
+        }
+    };
+
+    //Handles the printing of the MAC address
+    let result = MachineAddress {
+        macs,
+
+        //Provide the count of the arguments.  This is so the program knows
+        //if any arguments were passed and if it needs to provide a specific message
+        //when no arguments have been assigned.
+        arg_count: args.len(),
+
+        //Determines the separator and capitalization for hexadecimal output
+        notation,
+
+        //Drops the labels so the output can be read by other programs
+        plain: parsed_args.flag("plain"),
+
+        within: fixed_prefix,
+        format,
+        template,
+    }.print();
+
+    args::finish(result);
+}
+
+//Reads the -t/--template each address is printed through.  A template is text for people, so
+//it cannot be combined with a -F/--format for other programs.  Shared by every command that
+//prints addresses.
+pub fn select_template(parsed_args: &Args, format: Format) -> Option<Template> {
+    let value = parsed_args.value("template")?;
+
+    if format != Format::Text {
+        parsed_args.usage("-t/--template cannot be used with -F/--format");
+    }
+    if value.is_empty() {
+        parsed_args.usage("-t/--template needs a template, such as '{mac} {index}'");
+    }
+
+    match value.parse::<Template>() {
+        Ok(template) => Some(template),
+        Err(error) => parsed_args.usage(&format!("invalid template '{}': {}", value, error)),
+    }
+}
+
+//Chooses the entropy source from the -R/--rng, -S/--seed and -E/--entropy-file arguments.
+fn select_entropy(parsed_args: &Args) -> EntropySource {
+    let rng = parsed_args.choice("rng", &[("thread", EntropySource::Thread), ("os", EntropySource::Os)]);
+    let seed = parsed_args.parse_value::<u64>("seed", &format!("a whole number from 0 to {}", u64::MAX));
+    let entropy_file = parsed_args.value("entropy-file");
+
+    //Only one source can be used at a time
+    if [rng.is_some(), seed.is_some(), entropy_file.is_some()].iter().filter(|&&used| used).count() > 1 {
+        parsed_args.usage("only one of -R/--rng, -S/--seed and -E/--entropy-file can be used");
+    }
+
+    match (rng, seed, entropy_file) {
+        (Some(rng), _, _) => rng,
+        (_, Some(seed), _) => EntropySource::Seed(seed),
+        //A single dash on its own reads the entropy from standard input
+        (_, _, Some("")) => parsed_args.invalid("entropy-file", "", "a path, or - for standard input"),
+        (_, _, Some("-")) => EntropySource::Stdin,
+        (_, _, Some(path)) => EntropySource::File(path.into()),
+        (None, None, None) => EntropySource::Thread,
+    }
+}
+
+//Reads the fixed prefix from the -P/--prefix argument.  A prefix that is not locally administered
+//unicast is refused, since handing out addresses from it could clash with real hardware or be
+//treated as multicast.  -f/--force turns the refusal into a warning.
+pub fn select_prefix(parsed_args: &Args) -> Option<MacPrefix> {
+    let value = parsed_args.value("prefix")?;
+
+    let prefix = match value.parse::<MacPrefix>() {
+        Ok(prefix) => prefix,
+        Err(error) => parsed_args.usage(&format!("invalid prefix '{}': {}\n\
+            The prefix must be 1 to 5 octets, such as 02:5a:10, or a prefix length such as 02:5a:10:40:00:00/28.", value, error)),
+    };
+
+    if let Some(problem) = prefix_problem(&prefix) {
+        if parsed_args.flag("force") {
+            eprintln!("pmg: warning: prefix {} is {}", prefix, problem);
+        } else {
+            parsed_args.usage(&format!("prefix {} is {}, not locally administered unicast\n\
+                The second hexadecimal digit must be 2, 6, a or e.  Use -f/--force to use it anyway.", prefix, problem));
+        }
+    }
+
+    Some(prefix)
+}
+
+//Describes why a prefix is not locally administered unicast, or None if it is.  Shared with the
+//API, which refuses such a prefix just as -P/--prefix does without -f/--force.
+pub fn prefix_problem(prefix: &MacPrefix) -> Option<&'static str> {
+    if prefix.is_local_unicast() {
+        return None;
+    }
+
+    let first = prefix.first();
+    Some(match (first.is_local(), first.is_multicast()) {
+        (false, true) => "universally administered and multicast",
+        (false, false) => "universally administered",
+        _ => "multicast",
+    })
+}
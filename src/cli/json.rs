@@ -0,0 +1,376 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use std::fmt::{self, Write};
+
+//A JSON value for machine readable output.  Objects keep their keys in the order they were
+//added so the output always lists the fields the same way.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    //Numbers are kept as their text so 64 bit values are never rounded through a float
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+//How deeply arrays and objects can nest before parsing gives up, so a hostile request
+//cannot exhaust the stack
+const MAX_DEPTH: usize = 64;
+
+impl Json {
+    //Creates an object from its fields
+    pub fn object(fields: Vec<(&str, Json)>) -> Json {
+        Json::Object(fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+    }
+
+    //Parses a complete JSON document.  The error says what was wrong and where.
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            position: 0,
+        };
+
+        let value = parser.value(0)?;
+        parser.skip_whitespace();
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("unexpected text after the value"));
+        }
+
+        Ok(value)
+    }
+
+    //Returns the value of a field of an object, or None for a missing field or any other value
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref fields) => fields.iter().find(|&(name, _)| name == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Json::String(ref value) => Some(value),
+            _ => None,
+        }
+    }
+
+    //Returns a whole number that fits in 64 bits
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Json::Number(ref value) => value.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+//A recursive descent parser over the characters of a document
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn value(&mut self, depth: usize) -> Result<Json, String> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("the value is nested too deeply"));
+        }
+
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.object(depth),
+            Some('[') => self.array(depth),
+            Some('"') => Ok(Json::String(self.string()?)),
+            Some('t') => self.literal("true", Json::Bool(true)),
+            Some('f') => self.literal("false", Json::Bool(false)),
+            Some('n') => self.literal("null", Json::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("the document ends before a value")),
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Json, String> {
+        self.position += 1;
+        let mut fields = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Json::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected a field name"));
+            }
+            let key = self.string()?;
+
+            self.skip_whitespace();
+            self.expect(':')?;
+            let value = self.value(depth + 1)?;
+            fields.push((key, value));
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Json::Object(fields)),
+                _ => return Err(self.error("expected , or } after a field")),
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Json, String> {
+        self.position += 1;
+        let mut values = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+
+        loop {
+            values.push(self.value(depth + 1)?);
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Json::Array(values)),
+                _ => return Err(self.error("expected , or ] after a value")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.position += 1;
+        let mut output = String::new();
+
+        loop {
+            match self.next() {
+                Some('"') => return Ok(output),
+                Some('\\') => match self.next() {
+                    Some('"') => output.push('"'),
+                    Some('\\') => output.push('\\'),
+                    Some('/') => output.push('/'),
+                    Some('b') => output.push('\u{8}'),
+                    Some('f') => output.push('\u{c}'),
+                    Some('n') => output.push('\n'),
+                    Some('r') => output.push('\r'),
+                    Some('t') => output.push('\t'),
+                    Some('u') => output.push(self.unicode_escape()?),
+                    _ => return Err(self.error("invalid escape in a string")),
+                },
+                Some(c) if (c as u32) < 0x20 => return Err(self.error("control character in a string")),
+                Some(c) => output.push(c),
+                None => return Err(self.error("the document ends inside a string")),
+            }
+        }
+    }
+
+    //Reads the digits of a \u escape.  Characters outside the basic plane are written as two
+    //escapes, a high and a low surrogate.
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex4()?;
+        let code = if (0xd800..0xdc00).contains(&high) {
+            if self.next() != Some('\\') || self.next() != Some('u') {
+                return Err(self.error("unpaired surrogate in a string"));
+            }
+            let low = self.hex4()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(self.error("unpaired surrogate in a string"));
+            }
+            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            high
+        };
+
+        ::std::char::from_u32(code).ok_or_else(|| self.error("invalid character in a string"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self.next().and_then(|c| c.to_digit(16));
+            match digit {
+                Some(digit) => code = code * 16 + digit,
+                None => return Err(self.error("invalid \\u escape in a string")),
+            }
+        }
+
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+        if self.peek() == Some('-') {
+            self.position += 1;
+        }
+
+        //A leading zero cannot be followed by more digits
+        match self.peek() {
+            Some('0') => self.position += 1,
+            Some(c) if c.is_ascii_digit() => self.digits(),
+            _ => return Err(self.error("invalid number")),
+        }
+
+        if self.peek() == Some('.') {
+            self.position += 1;
+            if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return Err(self.error("invalid number"));
+            }
+            self.digits();
+        }
+
+        if let Some('e') | Some('E') = self.peek() {
+            self.position += 1;
+            if let Some('+') | Some('-') = self.peek() {
+                self.position += 1;
+            }
+            if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return Err(self.error("invalid number"));
+            }
+            self.digits();
+        }
+
+        Ok(Json::Number(self.chars[start..self.position].iter().collect()))
+    }
+
+    fn digits(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        for expected in word.chars() {
+            if self.next() != Some(expected) {
+                return Err(self.error("expected a value"));
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.next() == Some(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {}", expected)))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ') | Some('\t') | Some('\n') | Some('\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).cloned()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.position += 1;
+        c
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at character {}", message, self.position)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Json {
+        Json::Bool(value)
+    }
+}
+
+impl From<u64> for Json {
+    fn from(value: u64) -> Json {
+        Json::Number(value.to_string())
+    }
+}
+
+impl<'a> From<&'a str> for Json {
+    fn from(value: &'a str) -> Json {
+        Json::String(value.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Json {
+        Json::String(value)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Json {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+//Writes the value as compact JSON on a single line
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Number(ref value) => f.write_str(value),
+            Json::String(ref value) => write_string(f, value),
+            Json::Array(ref values) => {
+                f.write_char('[')?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(ref fields) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+//Writes a quoted string, escaping the characters JSON does not allow as they are
+fn write_string(f: &mut fmt::Formatter, value: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in value.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
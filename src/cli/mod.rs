@@ -0,0 +1,32 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Each subcommand lives in its own module and is started by main() through its run function,
+//which is given the arguments typed in after the subcommand's name.
+
+pub mod args;
+pub mod audit;
+pub mod check;
+pub mod convert;
+pub mod emit;
+pub mod gen;
+pub mod json;
+pub mod pool;
+pub mod prefix;
+pub mod range;
+pub mod record;
+pub mod serve;
+#[cfg(unix)]
+pub mod socket;
+pub mod time;
@@ -0,0 +1,827 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use pmg::{Allocation, Backend, MacAddress, Metadata, Notation, Pool, PoolError};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::process;
+
+use cli::args::{self, select_notation, Args, Opt};
+use cli::audit;
+use cli::gen::select_prefix;
+use cli::json::Json;
+use cli::record::{self, versioned, Format, RecordWriter};
+use cli::time::{format_duration, format_time, now, parse_duration};
+
+//How long a reaped address waits before it is handed out again, unless init is told otherwise
+const DEFAULT_QUARANTINE: &str = "1h";
+
+//The fields of an allocation record, as allocation_json writes them after schema_version
+const ALLOCATION_FIELDS: &[&str] = &["schema_version", "mac", "allocated", "expires", "ttl", "owner", "tags", "comment"];
+
+//The fields of an audit log record, as Event::to_json writes them after schema_version
+const EVENT_FIELDS: &[&str] = &["schema_version", "time", "op", "prefix", "mac", "owner", "tags", "expires", "user"];
+
+//The fields of a stats record, as stats_json writes them after schema_version
+const STATS_FIELDS: &[&str] = &["schema_version", "prefix", "total", "allocated", "expired", "quarantined", "free", "percent_used"];
+
+const HELP: &str = "Help file for pmg pool, a persistent pool of MAC addresses.
+
+SYNTAX
+    pmg pool init <file> [-P] <prefix> [-f] [[-q] <duration>] [[-b] <string>]
+    pmg pool alloc <file> [[-o] <string>] [[-T] <key=value>]... [[-C] <string>] [[-t] <duration>]
+    pmg pool renew <file> <mac> [[-t] <duration>]
+    pmg pool release <file> <mac>
+    pmg pool reap <file>
+    pmg pool list <file> [[-F] <string>]
+    pmg pool find <file> [[-T] <key=value or key>]... [[-o] <string>] [[-F] <string>]
+    pmg pool show <file> <mac> [[-F] <string>]
+    pmg pool stats <file> [[-F] <string>] [[-d] <directory>]
+    pmg pool history <file> <mac> [[-F] <string>]
+    pmg pool verify <file>
+    pmg pool fsck <file>
+
+ACTIONS
+    init            Creates a pool file for the addresses of a prefix.
+        -P  --prefix      The prefix to hand addresses out from, a /24 or longer.
+        -f  --force       Uses a prefix that is not locally administered unicast.
+        -q  --quarantine  How long a reaped address is held back after its lease
+                          ran out, so stale ARP caches can clear.
+                          Defaults:  1h
+        -b  --backend     How the pool file is written.  text writes one line per
+                          address, for pools kept in git.  See TEXT POOLS.
+                          Accepted Values:  binary text
+                          Defaults:  binary
+
+    alloc           Hands out the lowest free address and prints it.
+        -o  --owner       Who or what the address is for, such as vm-42.
+        -T  --tag         A key=value label, such as env=staging.  Can be used
+                          more than once.
+        -C  --comment     A note about the allocation.
+        -t  --ttl         Leases the address for this long.  Without a lease the
+                          address is held until it is released.
+
+    renew           Extends the lease of an address from now.
+        -t  --ttl         The length of the new lease.
+                          Defaults:  The length of the current lease.
+
+    release         Returns an address to the pool so it can be handed out again.
+
+    reap            Frees every address whose lease has run out and prints them.
+                    They are handed out again once their quarantine is over.
+
+    list            Prints every allocated address, when it was allocated, when
+                    its lease runs out, its owner and its tags.
+
+    find            Prints the allocations that match every -T/--tag and -o/--owner.
+        -T  --tag         key=value matches that value, key on its own matches any
+                          value.  Can be used more than once.
+        -o  --owner       Matches the owner.
+
+    show            Prints everything recorded about an allocated address.
+
+    stats           Prints how many addresses are allocated, expired, quarantined
+                    and free, and the percentage in use.
+        -F  --format        Also accepts prometheus, the text exposition format.
+        -d  --textfile-dir  Writes the prometheus metrics to a file in this
+                            directory instead, for node_exporter's textfile
+                            collector.  The file is replaced in a single step.
+
+    history         Prints every change made to an address, from the audit log.
+
+    verify          Replays the audit log and checks it agrees with the pool.
+                    Prints each disagreement and exits 1 if there are any.
+
+    fsck            Checks a pool file, printing every problem and exiting 1 if
+                    there are any.  For a text pool that includes addresses on
+                    more than one line after a merge.
+
+    -F  --format    How list, find, show, stats and history print their results.
+                    Accepted Values:  table json csv yaml tsv
+                    Defaults:  table
+                    Notes:  json prints an array, or a single object for show and
+                            stats.  csv and tsv have a heading row and write tags
+                            as key=value separated by spaces.  Every record has
+                            schema_version as its first field, versioned as the
+                            records of 'pmg gen -h' are.
+
+    -s, -c, -g and -n change how the addresses are written, as in 'pmg gen -h'.
+
+POOL FILES
+    The pool file records every allocated address, so an address is never handed
+    out twice.  It holds one bit per address in the prefix, so a /24 pool file is
+    about 2 MiB however many addresses are allocated.
+
+    Any number of pmg processes can use the same pool at once.  Each change locks
+    the pool, using a file named after it with .lock added, and replaces the pool
+    file in a single step.  A crash leaves the pool as it was before the change.
+
+TEXT POOLS
+    A text pool has a header, then one line per allocated or quarantined address,
+    sorted by address, so it can be kept in git and edited on branches.  Text pools
+    hand out a random free address rather than the lowest, so branches seldom take
+    the same one.  Mark the pool for git's union merge in .gitattributes, so lines
+    added on two branches are both kept, and run fsck after merging:
+
+        lab.pool merge=union
+
+    If two branches did take the same address, it is on two lines and the pool
+    cannot be used until one of them is deleted.  fsck names both lines and owners.
+    Keep the .lock, .log and .tmp files beside the pool out of git.
+
+AUDIT LOG
+    Every change is also appended to an audit log, a file named after the pool with
+    .log added.  Each line is a JSON object with time, op (init, alloc, renew, release
+    or reap), mac, owner, tags, expires and user fields, where user is the user ID
+    that made the change, such as uid:1000.  The log is written before the pool, so
+    no change is ever missing from it.
+
+DURATIONS
+    A number followed by s, m, h, d or w for seconds, minutes, hours, days or weeks,
+    such as 90s, 2h or 1h30m.  A number on its own is seconds.
+
+EXIT STATUS
+    0 on success, 1 when the pool could not be used or is full and 2 when a value
+    could not be used.
+
+EXAMPLES
+    pmg pool init lab.pool -P 02:5a:10:00:00:00/24
+    pmg pool alloc lab.pool -o vm-42
+    pmg pool alloc lab.pool -o ci-runner -t 2h
+    pmg pool renew lab.pool 02:5a:10:00:00:01
+    pmg pool release lab.pool 02:5a:10:00:00:00
+    pmg pool reap lab.pool
+    pmg pool list lab.pool
+    pmg pool alloc lab.pool -o vm-42 -T env=staging -T team=net -C \"build farm\"
+    pmg pool find lab.pool -T env=staging -F json
+    pmg pool list lab.pool -F csv > allocations.csv
+    pmg pool show lab.pool 02:5a:10:00:00:00
+    pmg pool stats lab.pool -F prometheus
+    pmg pool stats lab.pool -d /var/lib/node_exporter/textfile
+    pmg pool history lab.pool 02:5a:10:00:00:00
+    pmg pool verify lab.pool
+    pmg pool init lab.pool -P 02:5a:10:00:00:00/32 -b text
+    pmg pool fsck lab.pool";
+
+//Runs the pool subcommand.  The first value picks the action, and its options and the pool
+//file follow it.
+pub fn run(args: &[String]) {
+    let action = match args.first() {
+        Some(action) if !action.starts_with('-') => action.as_str(),
+        //Only -h/--help can come before the action
+        _ => {
+            Args::parse("pmg pool", &[args::HELP], args);
+            println!("{}", HELP);
+            return;
+        }
+    };
+
+    let options = match options(action) {
+        Some(options) => options,
+        None => usage(&format!("unknown pool action '{}'", action)),
+    };
+    let parsed_args = Args::parse("pmg pool", &options, &args[1..]);
+    if parsed_args.flag("help") {
+        println!("{}", HELP);
+        return;
+    }
+
+    let path = match parsed_args.positionals.first() {
+        Some(path) => path.as_str(),
+        None => usage(&format!("{} takes a pool file", action)),
+    };
+    let values = &parsed_args.positionals[1..];
+
+    let result = match action {
+        "init" => init(path, values, &parsed_args),
+        "alloc" => alloc(path, values, &parsed_args, &select_notation(&parsed_args)),
+        "renew" => renew(path, values, &parsed_args, &select_notation(&parsed_args)),
+        "release" => release(path, values, &select_notation(&parsed_args)),
+        "reap" => reap(path, values, &select_notation(&parsed_args)),
+        "list" => list(path, values, &parsed_args, &select_notation(&parsed_args)),
+        "find" => find(path, values, &parsed_args, &select_notation(&parsed_args)),
+        "show" => show(path, values, &parsed_args, &select_notation(&parsed_args)),
+        "stats" => stats(path, values, &parsed_args),
+        "history" => history(path, values, &parsed_args, &select_notation(&parsed_args)),
+        "verify" => verify(path, values),
+        _ => fsck(path, values),
+    };
+
+    if let Err(error) = result {
+        match error {
+            PoolError::Io(ref error) if args::closed_pipe(error) => {}
+            error => {
+                eprintln!("pmg: {}: {}", path, error);
+                process::exit(1);
+            }
+        }
+    }
+}
+
+//The options an action takes, or None if there is no such action.  Every action that prints
+//addresses also takes args::NOTATION.
+fn options(action: &str) -> Option<Vec<Opt>> {
+    let options: &[Opt] = match action {
+        "init" => &[
+            args::HELP,
+            Opt::value('P', "prefix"),
+            Opt::flag('f', "force"),
+            Opt::value('q', "quarantine"),
+            Opt::value('b', "backend"),
+        ],
+        "alloc" => &[
+            args::HELP,
+            Opt::value('o', "owner"),
+            Opt::value('T', "tag"),
+            Opt::value('C', "comment"),
+            Opt::value('t', "ttl"),
+        ],
+        "renew" => &[args::HELP, Opt::value('t', "ttl")],
+        "release" | "reap" => &[args::HELP],
+        "list" | "show" | "history" => &[args::HELP, Opt::value('F', "format")],
+        "find" => &[
+            args::HELP,
+            Opt::value('T', "tag"),
+            Opt::value('o', "owner"),
+            Opt::value('F', "format"),
+        ],
+        "stats" => return Some(vec![args::HELP, Opt::value('F', "format"), Opt::value('d', "textfile-dir")]),
+        "verify" | "fsck" => return Some(vec![args::HELP]),
+        _ => return None,
+    };
+
+    Some([options, args::NOTATION].concat())
+}
+
+//Creates an empty pool file
+fn init(path: &str, values: &[String], parsed_args: &Args) -> Result<(), PoolError> {
+    no_values("init", values);
+
+    let prefix = match select_prefix(parsed_args) {
+        Some(prefix) => prefix,
+        None => usage("init takes a -P/--prefix"),
+    };
+
+    let quarantine = select_duration(parsed_args, "quarantine")
+        .unwrap_or_else(|| parse_duration(DEFAULT_QUARANTINE).unwrap());
+
+    let backend = parsed_args.choice("backend", &[("binary", Backend::Binary), ("text", Backend::Text)])
+        .unwrap_or(Backend::Binary);
+
+    let mut pool = Pool::new(prefix)?;
+    pool.set_quarantine(quarantine);
+    pool.set_backend(backend);
+    pool.create(path)?;
+    audit::record_init(Path::new(path), &pool, &audit::local_user())?;
+
+    println!("Created pool {} for {} ({} addresses, quarantine {})",
+        path, prefix, pool.capacity(), format_duration(pool.quarantine()));
+    Ok(())
+}
+
+//Hands out the next free address
+fn alloc(path: &str, values: &[String], parsed_args: &Args, notation: &Notation) -> Result<(), PoolError> {
+    no_values("alloc", values);
+
+    let metadata = select_metadata(parsed_args);
+    let ttl = select_ttl(parsed_args);
+
+    let mac = audit::update(path, &audit::local_user(), |pool, changes| {
+        let allocation = pool.allocate(metadata, ttl, changes.now)?;
+        changes.record("alloc", allocation);
+        Ok(allocation.mac)
+    })?;
+
+    println!("{}", notation.address(&mac));
+    Ok(())
+}
+
+//Extends the lease of an address
+fn renew(path: &str, values: &[String], parsed_args: &Args, notation: &Notation) -> Result<(), PoolError> {
+    let mac = match values {
+        [mac] => parse_mac(mac),
+        _ => usage("renew takes a pool file and a MAC address"),
+    };
+    let ttl = select_ttl(parsed_args);
+
+    let lease = audit::update(path, &audit::local_user(), |pool, changes| {
+        let allocation = pool.renew(&mac, ttl, changes.now)?;
+        changes.record("renew", allocation);
+        Ok(allocation.lease)
+    })?;
+
+    if let Some(lease) = lease {
+        println!("Renewed {} until {}", notation.address(&mac), format_time(lease.expires));
+    }
+    Ok(())
+}
+
+//Returns an address to the pool
+fn release(path: &str, values: &[String], notation: &Notation) -> Result<(), PoolError> {
+    let mac = match values {
+        [mac] => parse_mac(mac),
+        _ => usage("release takes a pool file and a MAC address"),
+    };
+
+    audit::update(path, &audit::local_user(), |pool, changes| {
+        changes.record("release", &pool.release(&mac)?);
+        Ok(())
+    })?;
+
+    println!("Released {}", notation.address(&mac));
+    Ok(())
+}
+
+//Frees the addresses whose lease has run out
+fn reap(path: &str, values: &[String], notation: &Notation) -> Result<(), PoolError> {
+    no_values("reap", values);
+
+    let (reaped, quarantine, now) = audit::update(path, &audit::local_user(), |pool, changes| {
+        let reaped = pool.reap(changes.now);
+        for allocation in &reaped {
+            changes.record("reap", allocation);
+        }
+        Ok((reaped, pool.quarantine(), changes.now))
+    })?;
+
+    for allocation in reaped {
+        let expires = allocation.lease.map_or(now, |lease| lease.expires);
+        println!("Reaped {}  {}  reusable after {}",
+            notation.address(&allocation.mac),
+            allocation.metadata.owner.as_deref().unwrap_or("-"),
+            format_time(expires.saturating_add(quarantine)));
+    }
+    Ok(())
+}
+
+//Prints every allocation in address order
+fn list(path: &str, values: &[String], parsed_args: &Args, notation: &Notation) -> Result<(), PoolError> {
+    no_values("list", values);
+    let format = select_format(parsed_args);
+
+    let pool = Pool::open(path)?;
+    print_allocations(pool.allocations(), format, notation)
+}
+
+//Prints the allocations matching every tag and owner typed in
+fn find(path: &str, values: &[String], parsed_args: &Args, notation: &Notation) -> Result<(), PoolError> {
+    no_values("find", values);
+    let format = select_format(parsed_args);
+
+    //key=value matches that value and key on its own matches any value
+    let tags: Vec<(&str, Option<&str>)> = parsed_args.values("tag").into_iter()
+        .map(|tag| match tag.find('=') {
+            Some(index) => (&tag[..index], Some(&tag[index + 1..])),
+            None => (tag, None),
+        })
+        .collect();
+    let owner = parsed_args.value("owner");
+
+    if tags.iter().any(|&(key, _)| key.is_empty()) {
+        usage("a -T/--tag must be a key=value or a key");
+    }
+
+    let pool = Pool::open(path)?;
+    let found = pool.allocations().filter(|allocation| {
+        let metadata = &allocation.metadata;
+        tags.iter().all(|&(key, value)| metadata.has_tag(key, value))
+            && owner.is_none_or(|owner| metadata.owner.as_deref() == Some(owner))
+    });
+
+    print_allocations(found, format, notation)
+}
+
+//Prints everything recorded about one allocation
+fn show(path: &str, values: &[String], parsed_args: &Args, notation: &Notation) -> Result<(), PoolError> {
+    let mac = match values {
+        [mac] => parse_mac(mac),
+        _ => usage("show takes a pool file and a MAC address"),
+    };
+    let format = select_format(parsed_args);
+
+    let pool = Pool::open(path)?;
+    let allocation = match pool.get(&mac) {
+        Some(allocation) => allocation,
+        None if pool.prefix().contains(&mac) => return Err(PoolError::NotAllocated(mac)),
+        None => return Err(PoolError::OutsidePool(mac)),
+    };
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    match format {
+        Format::Json => writeln!(out, "{}", versioned(allocation_json(allocation, notation)))?,
+        Format::Csv | Format::Yaml | Format::Tsv => {
+            let mut writer = RecordWriter::new(&mut out, format, ALLOCATION_FIELDS)?;
+            writer.write(&versioned(allocation_json(allocation, notation)))?;
+            writer.finish()?;
+        }
+        Format::Text => {
+            let metadata = &allocation.metadata;
+            writeln!(out, "Address:               {}", notation.address(&allocation.mac))?;
+            writeln!(out, "Owner:                 {}", metadata.owner.as_deref().unwrap_or("-"))?;
+            writeln!(out, "Allocated:             {}", format_time(allocation.allocated))?;
+            match allocation.lease {
+                Some(lease) => {
+                    writeln!(out, "Expires:               {}", format_time(lease.expires))?;
+                    writeln!(out, "Lease:                 {}", format_duration(lease.ttl))?;
+                }
+                None => writeln!(out, "Expires:               never")?,
+            }
+            writeln!(out, "Tags:                  {}", format_tags(metadata))?;
+            writeln!(out, "Comment:               {}", metadata.comment.as_deref().unwrap_or("-"))?;
+        }
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+//Prints how much of the pool is in use, or writes it for node_exporter
+fn stats(path: &str, values: &[String], parsed_args: &Args) -> Result<(), PoolError> {
+    no_values("stats", values);
+
+    //Prometheus is only offered here, so -F/--format is read without select_format
+    let format = parsed_args.value("format");
+    let textfile_dir = parsed_args.value("textfile-dir");
+
+    let records = ["json", "csv", "yaml", "tsv"];
+    match (format, textfile_dir) {
+        (None, _) | (Some("prometheus"), _) => {}
+        (Some(value), None) if value == "table" || records.contains(&value) => {}
+        (Some(value), Some(_)) if value == "table" || records.contains(&value) => {
+            usage("-d/--textfile-dir writes prometheus metrics and cannot be used with another -F/--format")
+        }
+        (Some(value), _) => parsed_args.invalid("format", value, "table, json, csv, yaml, tsv or prometheus"),
+    }
+
+    let pool = Pool::open(path)?;
+    let now = now();
+
+    if let Some(dir) = textfile_dir {
+        return write_textfile(Path::new(dir), path, &prometheus_metrics(&pool, path, now));
+    }
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    match format {
+        Some("json") => writeln!(out, "{}", versioned(stats_json(&pool, now)))?,
+        Some("prometheus") => out.write_all(prometheus_metrics(&pool, path, now).as_bytes())?,
+        Some(value) if value != "table" => {
+            let format = record::select_format(parsed_args, "table");
+            let mut writer = RecordWriter::new(&mut out, format, STATS_FIELDS)?;
+            writer.write(&versioned(stats_json(&pool, now)))?;
+            writer.finish()?;
+        }
+        _ => {
+            let usage = pool.usage(now);
+            writeln!(out, "Prefix:                {}", pool.prefix())?;
+            writeln!(out, "Total:                 {}", usage.capacity)?;
+            writeln!(out, "Allocated:             {}", usage.allocated)?;
+            writeln!(out, "Expired:               {}", usage.expired)?;
+            writeln!(out, "Quarantined:           {}", usage.quarantined)?;
+            writeln!(out, "Free:                  {}", usage.free)?;
+            writeln!(out, "Used:                  {:.2}%", usage.used_percent())?;
+        }
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+//The pool's usage in the Prometheus text exposition format, labelled with the pool file
+//and its prefix.  Use is a ratio from 0 to 1, as Prometheus prefers.
+fn prometheus_metrics(pool: &Pool, path: &str, now: u64) -> String {
+    let usage = pool.usage(now);
+    let labels = format!("pool=\"{}\",prefix=\"{}\"", escape_label(path), pool.prefix());
+    let metrics = [
+        ("capacity", "Addresses in the pool.", usage.capacity.to_string()),
+        ("allocated", "Addresses handed out, including expired leases.", usage.allocated.to_string()),
+        ("expired", "Allocated addresses whose lease has run out and that have not been reaped.", usage.expired.to_string()),
+        ("quarantined", "Reaped addresses waiting out their quarantine.", usage.quarantined.to_string()),
+        ("free", "Addresses that can be handed out.", usage.free.to_string()),
+        ("used_ratio", "Share of the pool allocated or quarantined, from 0 to 1.", format!("{:.6}", usage.used_percent() / 100.0)),
+    ];
+
+    let mut output = String::new();
+    for &(name, help, ref value) in metrics.iter() {
+        output.push_str(&format!("# HELP pmg_pool_{} {}\n# TYPE pmg_pool_{} gauge\npmg_pool_{}{{{}}} {}\n",
+            name, help, name, name, labels, value));
+    }
+
+    output
+}
+
+//Escapes a label value as the exposition format requires
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+//Writes metrics to pmg_pool_<name>.prom in `dir`, where name comes from the pool file.  The
+//collector only reads files ending in .prom, so the metrics are written to a temporary name
+//first and renamed, and it never sees a partly written file.
+fn write_textfile(dir: &Path, path: &str, metrics: &str) -> Result<(), PoolError> {
+    let name: String = Path::new(path).file_stem().map_or("pool".into(), |stem| stem.to_string_lossy())
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect();
+    let target = dir.join(format!("pmg_pool_{}.prom", name));
+    let temporary = dir.join(format!(".pmg_pool_{}.prom.{}.tmp", name, process::id()));
+
+    let result = File::create(&temporary)
+        .and_then(|mut file| {
+            file.write_all(metrics.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temporary, &target));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temporary);
+    }
+    result.map_err(PoolError::Io)
+}
+
+//Prints every change made to one address, oldest first
+fn history(path: &str, values: &[String], parsed_args: &Args, notation: &Notation) -> Result<(), PoolError> {
+    let mac = match values {
+        [mac] => parse_mac(mac),
+        _ => usage("history takes a pool file and a MAC address"),
+    };
+    let format = select_format(parsed_args);
+
+    let mut events = Vec::new();
+    for (line, event) in audit::read(Path::new(path))? {
+        match event {
+            Ok(event) => if event.mac == Some(mac) {
+                events.push(event);
+            },
+            Err(error) => eprintln!("pmg: {}: line {}: unreadable entry: {}", audit::log_path(Path::new(path)).display(), line, error),
+        }
+    }
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    match format {
+        Format::Json => writeln!(out, "{}", Json::Array(events.iter().map(|event| versioned(event.to_json())).collect()))?,
+        Format::Csv | Format::Yaml | Format::Tsv => {
+            let mut writer = RecordWriter::new(&mut out, format, EVENT_FIELDS)?;
+            for event in &events {
+                writer.write(&versioned(event.to_json()))?;
+            }
+            writer.finish()?;
+        }
+        Format::Text => {
+            let mut rows = vec![["TIME", "OP", "ADDRESS", "EXPIRES", "OWNER", "TAGS", "USER"].iter().map(|heading| heading.to_string()).collect()];
+            for event in &events {
+                let metadata = Metadata {
+                    owner: event.owner.clone(),
+                    tags: event.tags.clone(),
+                    comment: None,
+                };
+                rows.push(vec![
+                    format_time(event.time),
+                    event.op.clone(),
+                    notation.address(&mac),
+                    event.expires.map_or("-".to_string(), format_time),
+                    metadata.owner.clone().unwrap_or_else(|| "-".to_string()),
+                    format_tags(&metadata),
+                    event.user.clone(),
+                ]);
+            }
+            write_table(&mut out, &rows)?;
+        }
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+//Checks the audit log against the pool, printing every disagreement
+fn verify(path: &str, values: &[String]) -> Result<(), PoolError> {
+    no_values("verify", values);
+
+    //The lock keeps the pool and the log from changing between reading one and the other
+    let (problems, entries) = {
+        let _lock = Pool::lock(path)?;
+        let pool = Pool::open(path)?;
+        audit::verify(Path::new(path), &pool)?
+    };
+
+    if problems.is_empty() {
+        println!("{}: the audit log agrees with the pool ({} entries)", path, entries);
+        return Ok(());
+    }
+
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    eprintln!("pmg: {}: {} problems found in the audit log", path, problems.len());
+    process::exit(1);
+}
+
+//Checks the pool file for damage, such as an address left on two lines by a merge
+fn fsck(path: &str, values: &[String]) -> Result<(), PoolError> {
+    no_values("fsck", values);
+
+    let problems = {
+        let _lock = Pool::lock(path)?;
+        Pool::fsck(path)?
+    };
+
+    if problems.is_empty() {
+        println!("{}: no problems found", path);
+        return Ok(());
+    }
+
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    eprintln!("pmg: {}: {} problems found", path, problems.len());
+    process::exit(1);
+}
+
+//Prints allocations as a table with a heading, or as records
+fn print_allocations<'a, I>(allocations: I, format: Format, notation: &Notation) -> Result<(), PoolError>
+    where I: Iterator<Item = &'a Allocation>
+{
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+
+    match format {
+        Format::Json => {
+            let array = Json::Array(allocations.map(|allocation| versioned(allocation_json(allocation, notation))).collect());
+            writeln!(out, "{}", array)?;
+        }
+        Format::Csv | Format::Yaml | Format::Tsv => {
+            let mut writer = RecordWriter::new(&mut out, format, ALLOCATION_FIELDS)?;
+            for allocation in allocations {
+                writer.write(&versioned(allocation_json(allocation, notation)))?;
+            }
+            writer.finish()?;
+        }
+        Format::Text => {
+            let mut rows = vec![["ADDRESS", "ALLOCATED", "EXPIRES", "OWNER", "TAGS"].iter().map(|heading| heading.to_string()).collect()];
+            for allocation in allocations {
+                let metadata = &allocation.metadata;
+                rows.push(vec![
+                    notation.address(&allocation.mac),
+                    format_time(allocation.allocated),
+                    //An address without a lease is held until it is released
+                    allocation.lease.map_or("never".to_string(), |lease| format_time(lease.expires)),
+                    metadata.owner.as_deref().unwrap_or("-").to_string(),
+                    format_tags(metadata),
+                ]);
+            }
+            write_table(&mut out, &rows)?;
+        }
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+//Writes rows with each column padded to its widest value.  The last column is not padded.
+fn write_table(out: &mut dyn Write, rows: &[Vec<String>]) -> io::Result<()> {
+    let columns = rows.first().map_or(0, |row| row.len());
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| rows.iter().map(|row| row[column].chars().count()).max().unwrap_or(0))
+        .collect();
+
+    for row in rows {
+        let mut line = String::new();
+        for (column, value) in row.iter().enumerate() {
+            if column + 1 < columns {
+                line.push_str(&format!("{:<width$}  ", value, width = widths[column]));
+            } else {
+                line.push_str(value);
+            }
+        }
+        writeln!(out, "{}", line)?;
+    }
+
+    Ok(())
+}
+
+//The fields of an allocation as a JSON object.  Times are RFC 3339 and lease lengths seconds.
+pub fn allocation_json(allocation: &Allocation, notation: &Notation) -> Json {
+    let metadata = &allocation.metadata;
+    let tags = metadata.tags.iter()
+        .map(|(key, value)| (key.clone(), Json::from(value.as_str())))
+        .collect();
+
+    Json::object(vec![
+        ("mac", notation.address(&allocation.mac).into()),
+        ("allocated", format_time(allocation.allocated).into()),
+        ("expires", allocation.lease.map(|lease| format_time(lease.expires)).into()),
+        ("ttl", allocation.lease.map(|lease| lease.ttl).into()),
+        ("owner", metadata.owner.as_deref().into()),
+        ("tags", Json::Object(tags)),
+        ("comment", metadata.comment.as_deref().into()),
+    ])
+}
+
+//How much of a pool is in use at `now` as a JSON object
+pub fn stats_json(pool: &Pool, now: u64) -> Json {
+    let usage = pool.usage(now);
+
+    Json::object(vec![
+        ("prefix", pool.prefix().to_string().into()),
+        ("total", usage.capacity.into()),
+        ("allocated", usage.allocated.into()),
+        ("expired", usage.expired.into()),
+        ("quarantined", usage.quarantined.into()),
+        ("free", usage.free.into()),
+        ("percent_used", Json::Number(format!("{:.2}", usage.used_percent()))),
+    ])
+}
+
+//Tags written as key=value separated by spaces, or - when there are none
+fn format_tags(metadata: &Metadata) -> String {
+    if metadata.tags.is_empty() {
+        return "-".to_string();
+    }
+
+    metadata.tags.iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+//Reads the owner, tags and comment to record with an allocation
+fn select_metadata(parsed_args: &Args) -> Metadata {
+    let owner = parsed_args.value("owner");
+    let comment = parsed_args.value("comment");
+
+    let mut metadata = Metadata {
+        owner: owner.map(|owner| owner.to_string()),
+        comment: comment.map(|comment| comment.to_string()),
+        ..Metadata::default()
+    };
+
+    for tag in parsed_args.values("tag") {
+        match tag.find('=') {
+            Some(index) if index > 0 => {
+                metadata.tags.insert(tag[..index].to_string(), tag[index + 1..].to_string());
+            }
+            _ => parsed_args.invalid("tag", tag, "key=value"),
+        }
+    }
+
+    metadata
+}
+
+//Reads -F/--format, see cli::record
+fn select_format(parsed_args: &Args) -> Format {
+    record::select_format(parsed_args, "table")
+}
+
+//Reads a duration argument such as -t/--ttl, or None if it was not used
+fn select_duration(parsed_args: &Args, gnu: &str) -> Option<u64> {
+    let value = parsed_args.value(gnu)?;
+
+    match parse_duration(value) {
+        Some(seconds) => Some(seconds),
+        None => parsed_args.invalid(gnu, value, "a duration such as 90s, 30m, 2h or 1d"),
+    }
+}
+
+//Reads the -t/--ttl lease length.  A lease must last for some time.
+fn select_ttl(parsed_args: &Args) -> Option<u64> {
+    let ttl = select_duration(parsed_args, "ttl");
+    if ttl == Some(0) {
+        usage("a -t/--ttl lease must be longer than 0s");
+    }
+
+    ttl
+}
+
+//Parses a MAC address or stops with a usage error
+fn parse_mac(value: &str) -> MacAddress {
+    match value.parse::<MacAddress>() {
+        Ok(mac) => mac,
+        Err(error) => usage(&format!("invalid MAC address '{}': {}", value, error)),
+    }
+}
+
+//Stops with a usage error if values were typed in after the pool file
+fn no_values(action: &str, values: &[String]) {
+    if !values.is_empty() {
+        usage(&format!("{} takes only a pool file", action));
+    }
+}
+
+//Prints a usage error and stops
+fn usage(message: &str) -> ! {
+    args::usage("pmg pool", message)
+}
@@ -0,0 +1,270 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use pmg::{self, MacAddress, MacPrefix, Notation};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::process;
+
+use cli::args::{self, Args, Opt};
+use cli::json::Json;
+use cli::record::{self, Format, RecordWriter};
+
+//Exit status when a check such as contains or overlap is false
+const EXIT_FALSE: i32 = 1;
+
+const HELP: &str = "Help file for pmg prefix, MAC prefix calculations.
+
+SYNTAX
+    pmg prefix contains <prefix> <mac or prefix> [[-F] <string>]
+    pmg prefix overlap <prefix> <prefix> [[-F] <string>]
+    pmg prefix split <prefix> [[-i] <integer> | [-l] <integer>] [[-F] <string>]
+    pmg prefix summarize [<prefix>...] [[-F] <string>]
+
+ACTIONS
+    contains        Checks whether a MAC address or prefix is inside the prefix.
+                    Exits with 0 if it is and 1 if it is not.
+
+    overlap         Checks whether two prefixes share any addresses, and prints the
+                    shared prefix if they do.  Exits with 0 if they overlap and 1 if not.
+
+    split           Divides a prefix into smaller prefixes of equal size.
+        -i  --into      The number of prefixes to divide into, a power of two.
+                        Defaults:  2
+        -l  --length    The length of the smaller prefixes in bits.
+                        Overrides: -i/--into.
+
+    summarize       Collapses prefixes into the fewest prefixes covering the same
+                    addresses.  Reads one prefix per line from standard input when
+                    no prefixes are typed in.
+
+OPTIONS
+    -F  --format    How the output is written.
+                    Accepted Values:  text json csv yaml tsv
+                    Defaults:  text
+                    Notes:  Every format but text prints one record per prefix, as
+                            described in 'pmg gen -h'.  contains prints the address or
+                            prefix checked and overlap the shared prefix, or no
+                            records when the check is false.
+
+PREFIXES
+    Prefixes are written as an address and length, 02:5a:10:40:00:00/28, or as 1 to 5
+    octets, 02:5a:10.  Prefix lengths from 8 to 47 bits are accepted.
+
+EXIT STATUS
+    0 when the check is true, 1 when it is false and 2 when a value could not be used.
+
+EXAMPLES
+    pmg prefix contains 02:5a:10/24 02:5a:10:00:00:01
+    pmg prefix overlap 02:5a:10/24 02:5a:10:40:00:00/28
+    pmg prefix split 02:5a:10/24 -i 16
+    pmg prefix split 02:5a:10/24 -i 16 -F csv
+    cat blocks.txt | pmg prefix summarize";
+
+//Runs the prefix subcommand.  The first value picks the action, and its options and values
+//follow it.
+pub fn run(args: &[String]) {
+    let action = match args.first() {
+        Some(action) if !action.starts_with('-') => action.as_str(),
+        //Only -h/--help can come before the action
+        _ => {
+            Args::parse("pmg prefix", &[args::HELP], args);
+            println!("{}", HELP);
+            return;
+        }
+    };
+
+    let options: &[Opt] = match action {
+        "split" => &[args::HELP, Opt::value('F', "format"), Opt::value('i', "into"), Opt::value('l', "length")],
+        "contains" | "overlap" | "summarize" => &[args::HELP, Opt::value('F', "format")],
+        action => usage(&format!("unknown prefix action '{}'", action)),
+    };
+    let parsed_args = Args::parse("pmg prefix", options, &args[1..]);
+    if parsed_args.flag("help") {
+        println!("{}", HELP);
+        return;
+    }
+
+    let format = record::select_format(&parsed_args, "text");
+    let values = &parsed_args.positionals;
+    let result = match action {
+        "contains" => contains(values, format),
+        "overlap" => overlap(values, format),
+        "split" => split(values, &parsed_args, format),
+        _ => summarize(values, format),
+    };
+
+    args::finish(result);
+}
+
+//Prints whether the second value is inside the first prefix
+fn contains(values: &[String], format: Format) -> io::Result<()> {
+    let (prefix, target) = match values {
+        [prefix, target] => (parse_prefix(prefix), target),
+        _ => usage("contains takes a prefix and a MAC address or prefix"),
+    };
+
+    //The value is tried as an address first, then as a prefix
+    let (inside, shown, found) = match target.parse::<MacAddress>() {
+        Ok(mac) => (prefix.contains(&mac), mac.to_string(), record::address_record(&mac, Some(&prefix), &Notation::default())),
+        Err(_) => {
+            let other = parse_prefix(target);
+            (prefix.contains_prefix(&other), other.to_string(), record::prefix_record(&other, &Notation::default()))
+        }
+    };
+
+    if format != Format::Text {
+        print_records(format, if inside { Some(found) } else { None })?;
+        if !inside {
+            process::exit(EXIT_FALSE);
+        }
+        return Ok(());
+    }
+
+    if inside {
+        println!("{} contains {}", prefix, shown);
+        Ok(())
+    } else {
+        println!("{} does not contain {}", prefix, shown);
+        process::exit(EXIT_FALSE);
+    }
+}
+
+//Prints whether two prefixes share addresses, and the shared prefix if they do
+fn overlap(values: &[String], format: Format) -> io::Result<()> {
+    let (a, b) = match values {
+        [a, b] => (parse_prefix(a), parse_prefix(b)),
+        _ => usage("overlap takes two prefixes"),
+    };
+
+    //Prefixes either nest or are disjoint, so the shared block is the longer prefix
+    let shared = if a.overlaps(&b) {
+        Some(if a.prefix_len() >= b.prefix_len() { a } else { b })
+    } else {
+        None
+    };
+
+    if format != Format::Text {
+        print_records(format, shared.map(|shared| record::prefix_record(&shared, &Notation::default())))?;
+        if shared.is_none() {
+            process::exit(EXIT_FALSE);
+        }
+        return Ok(());
+    }
+
+    if let Some(shared) = shared {
+        println!("{} overlaps {}", a, b);
+        println!("Shared Prefix:         {}", shared);
+        println!("Shared Addresses:      {}", shared.count());
+        Ok(())
+    } else {
+        println!("{} does not overlap {}", a, b);
+        process::exit(EXIT_FALSE);
+    }
+}
+
+//Prints the smaller prefixes the prefix divides into
+fn split(values: &[String], parsed_args: &Args, format: Format) -> io::Result<()> {
+    let prefix = match values {
+        [prefix] => parse_prefix(prefix),
+        _ => usage("split takes a single prefix"),
+    };
+
+    let subnets = match parsed_args.parse_value::<u8>("length", "a whole number from 8 to 47") {
+        Some(len) => prefix.subnets(len),
+        None => {
+            const POWER_OF_TWO: &str = "a power of two from 2 up, such as 2, 4 or 16";
+            let into = parsed_args.parse_value::<u64>("into", POWER_OF_TWO).unwrap_or(2);
+            //Only a power of two divides a prefix into equal prefixes
+            if into < 2 || !into.is_power_of_two() {
+                parsed_args.invalid("into", parsed_args.value("into").unwrap_or_default(), POWER_OF_TWO);
+            }
+            prefix.split(into)
+        }
+    };
+
+    let subnets = match subnets {
+        Some(subnets) => subnets,
+        None => usage(&format!("{} cannot be split that way; the smaller prefixes must be {} to 47 bits long",
+            prefix, prefix.prefix_len() + 1)),
+    };
+
+    print_prefixes(subnets, format)
+}
+
+//Prints the fewest prefixes covering the prefixes typed in, or read from standard input
+fn summarize(values: &[String], format: Format) -> io::Result<()> {
+    let mut prefixes: Vec<MacPrefix> = values.iter().map(|value| parse_prefix(value)).collect();
+
+    if values.is_empty() {
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            let line = line?;
+            //Blank lines and comments are skipped so prefix lists can be annotated
+            let value = line.trim();
+            if value.is_empty() || value.starts_with('#') {
+                continue;
+            }
+            prefixes.push(parse_prefix(value));
+        }
+    }
+
+    print_prefixes(pmg::summarize(&prefixes), format)
+}
+
+//Prints prefixes one per line, or as records
+fn print_prefixes<I: IntoIterator<Item = MacPrefix>>(prefixes: I, format: Format) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+
+    if format == Format::Text {
+        for prefix in prefixes {
+            writeln!(out, "{}", prefix)?;
+        }
+    } else {
+        let mut writer = RecordWriter::new(&mut out, format, record::ADDRESS_FIELDS)?;
+        for prefix in prefixes {
+            writer.write(&record::prefix_record(&prefix, &Notation::default()))?;
+        }
+        writer.finish()?;
+    }
+
+    out.flush()
+}
+
+//Prints the record a check found, or no records when it found nothing
+fn print_records(format: Format, found: Option<Json>) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    let mut writer = RecordWriter::new(&mut out, format, record::ADDRESS_FIELDS)?;
+    if let Some(found) = found {
+        writer.write(&found)?;
+    }
+    writer.finish()?;
+
+    out.flush()
+}
+
+//Parses a prefix or stops with a usage error
+fn parse_prefix(value: &str) -> MacPrefix {
+    match value.parse::<MacPrefix>() {
+        Ok(prefix) => prefix,
+        Err(error) => usage(&format!("invalid prefix '{}': {}", value, error)),
+    }
+}
+
+//Prints a usage error and stops
+fn usage(message: &str) -> ! {
+    args::usage("pmg prefix", message)
+}
@@ -0,0 +1,290 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use pmg::{Entropy, EntropySource, MacAddress, MacPrefix, Notation, Template};
+use std::io::{self, BufWriter, Write};
+use std::process;
+
+use cli::args::{self, select_notation, Args, Opt};
+use cli::gen::select_template;
+use cli::record::{self, Format, RecordWriter};
+
+//The options range takes, as well as args::NOTATION
+const OPTIONS: &[Opt] = &[
+    args::HELP,
+    Opt::flag('L', "list"),
+    Opt::flag('x', "shuffle"),
+    Opt::value('k', "key"),
+    Opt::flag('v', "verbose"),
+    Opt::value('o', "start"),
+    Opt::value('m', "limit"),
+    Opt::value('F', "format"),
+    Opt::value('t', "template"),
+];
+
+//Listing millions of addresses is bound by the number of writes, so the buffer is
+//larger than the BufWriter default
+const BUFFER_SIZE: usize = 1 << 16;
+
+const HELP: &str = "Help file for pmg range, the addresses inside a MAC prefix.
+
+SYNTAX
+    pmg range <prefix> [-L | -x [[-k] <integer>]] [-v] [[-o] <integer or mac>] [[-m] <integer>] [[-F] <string> | [-t] <string>] [[-s] <string>] [[-c] <string>] [[-g] <integer>] [-n]
+
+DESCRIPTION
+    Without -L/--list or -x/--shuffle the prefix, its number of addresses and its
+    first and last address are printed.  With -L/--list every address is printed,
+    one per line, in address order.  With -x/--shuffle every address is printed
+    exactly once in a pseudorandom order chosen by the key.
+
+OPTIONS
+    -L  --list          Prints every address in the prefix.
+    -x  --shuffle       Prints every address in the prefix in a shuffled order.
+    -k  --key           The key that chooses the shuffled order.
+                        Accepted Values:  Any whole number up to 18446744073709551615
+                        Defaults:  A random key, which is printed to stderr.
+                        Notes:  The same key always gives the same order.
+    -o  --start         The first address to print.
+                        Accepted Values:  An offset into the order the addresses are
+                                          printed in, where 0 is the first address,
+                                          or a MAC address inside the prefix.
+                                          Twelve digits with no separators are
+                                          a MAC address.
+                        Defaults:  0
+                        Notes:  With -x/--shuffle the offset is a cursor.  Starting
+                                from the number of addresses already printed
+                                continues the same shuffled order.  Needs -L/--list
+                                or -x/--shuffle.
+    -m  --limit         The most addresses to print.
+                        Defaults:  Every address to the end of the prefix.
+                        Notes:  Needs -L/--list or -x/--shuffle.
+    -F  --format        How the output is written.
+                        Accepted Values:  text json csv yaml tsv
+                        Defaults:  text
+                        Notes:  Every format but text prints one record per address,
+                                or one for the prefix, as described in 'pmg gen -h'.
+    -t  --template      Prints each address through a template, as described in
+                        'pmg gen -h'.  {index} is the address's offset, as -o/--start
+                        counts it.  Without -L/--list or -x/--shuffle the prefix
+                        is printed through it once.
+    -v  --verbose       Prints the cursor to continue from to stderr.
+
+    -s, -c, -g and -n change how the addresses are written, as in 'pmg gen -h'.
+
+PREFIXES
+    Prefixes are written as an address and length, 02:5a:10:40:00:00/28, or as 1 to 5
+    octets, 02:5a:10.  Prefix lengths from 8 to 47 bits are accepted.
+
+EXAMPLES
+    pmg range 02:5a:10:00:00:00/24
+    pmg range 02:5a:10:00:00:00/24 --list > addresses.txt
+    pmg range 02:5a:10/24 -L -o 1000 -m 100
+    pmg range 02:5a:10/24 -L -o 02:5a:10:00:10:00 -m 16 -s .
+    pmg range 02:5a:10/24 -x -k 42 -m 1000 -v
+    pmg range 02:5a:10/24 -x -k 42 -o 1000 -m 1000
+    pmg range 02:5a:10/24 -L -m 16 -F csv
+    pmg range 02:5a:10/24 -L -m 16 -t 'host-{index:1} {mac:upper:none}'";
+
+//Runs the range subcommand
+pub fn run(args: &[String]) {
+    let parsed_args = Args::parse("pmg range", &[OPTIONS, args::NOTATION].concat(), args);
+
+    if parsed_args.flag("help") || parsed_args.positionals.is_empty() {
+        println!("{}", HELP);
+        return;
+    }
+
+    let prefix = match parsed_args.positionals.as_slice() {
+        [value] => match value.parse::<MacPrefix>() {
+            Ok(prefix) => prefix,
+            Err(error) => usage(&format!("invalid prefix '{}': {}", value, error)),
+        },
+        _ => usage("range takes a single prefix"),
+    };
+
+    let notation = select_notation(&parsed_args);
+    let format = record::select_format(&parsed_args, "text");
+    let template = select_template(&parsed_args, format);
+    let output = Output {
+        notation: &notation,
+        format,
+        template: template.as_ref(),
+    };
+
+    let list = parsed_args.flag("list");
+    let shuffle = parsed_args.flag("shuffle");
+    let verbose = parsed_args.flag("verbose");
+    if parsed_args.flag("key") && !shuffle {
+        usage("-k/--key orders a -x/--shuffle and cannot be used without one");
+    }
+    if (parsed_args.flag("start") || parsed_args.flag("limit")) && !list && !shuffle {
+        usage("-o/--start and -m/--limit pick addresses from a -L/--list or -x/--shuffle and cannot be used without one");
+    }
+
+    let limit = select_limit(&parsed_args);
+    let result = if shuffle {
+        let walk = prefix.shuffled(select_key(&parsed_args));
+        //An address is started from wherever it falls in the shuffled order
+        let start = select_start(&parsed_args, &prefix, |mac| walk.position(mac).unwrap());
+        print_list(walk.skip(start as usize), &prefix, &output, limit, start, verbose)
+    } else if list {
+        let start = select_start(&parsed_args, &prefix, |mac| u64::from(*mac) - u64::from(prefix.first()));
+        print_list(prefix.addresses().skip(start as usize), &prefix, &output, limit, start, verbose)
+    } else {
+        print_summary(&prefix, &output)
+    };
+
+    args::finish(result);
+}
+
+//How addresses are written out, chosen by the notation arguments, -F/--format and -t/--template
+struct Output<'a> {
+    notation: &'a Notation,
+    format: Format,
+    template: Option<&'a Template>,
+}
+
+//Prints the size and ends of the prefix
+fn print_summary(prefix: &MacPrefix, output: &Output) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let notation = output.notation;
+
+    if output.format != Format::Text {
+        let mut writer = RecordWriter::new(&mut out, output.format, record::ADDRESS_FIELDS)?;
+        writer.write(&record::prefix_record(prefix, notation))?;
+        writer.finish()?;
+        return out.flush();
+    }
+    if let Some(template) = output.template {
+        let mut line = String::new();
+        template.write(&mut line, notation, &prefix.first(), Some(prefix), 0);
+        writeln!(out, "{}", line)?;
+        return out.flush();
+    }
+
+    writeln!(out, "Private MAC Prefix:    {}/{}", notation.address(&prefix.first()), prefix.prefix_len())?;
+    writeln!(out, "Assignable Addresses:  {}", prefix.count())?;
+    writeln!(out, "Assigned Addresses:    {} - {}",
+        notation.address(&prefix.first()),
+        notation.address(&prefix.last()))?;
+
+    out.flush()
+}
+
+//Prints up to `limit` addresses of `prefix`.  `start` is the offset of the first one, so the
+//cursor to continue from can be shown.
+fn print_list<I>(addresses: I, prefix: &MacPrefix, output: &Output, limit: Option<u64>, start: u64, verbose: bool) -> io::Result<()>
+    where I: Iterator<Item = MacAddress>
+{
+    let stdout = io::stdout();
+    let mut out = BufWriter::with_capacity(BUFFER_SIZE, stdout.lock());
+    let addresses = addresses.take(limit.map_or(usize::MAX, |limit| limit as usize));
+    let notation = output.notation;
+    let mut printed: u64 = 0;
+
+    if output.format == Format::Text {
+        //Each line is formatted into the same buffer rather than allocating a string per address
+        let mut line = String::with_capacity(32);
+        for mac in addresses {
+            line.clear();
+            match output.template {
+                Some(template) => template.write(&mut line, notation, &mac, Some(prefix), start + printed),
+                None => notation.write_octets(&mac.octets(), &mut line),
+            }
+            line.push('\n');
+            out.write_all(line.as_bytes())?;
+            printed += 1;
+        }
+    } else {
+        let mut writer = RecordWriter::new(&mut out, output.format, record::ADDRESS_FIELDS)?;
+        for mac in addresses {
+            writer.write(&record::address_record(&mac, Some(prefix), notation))?;
+            printed += 1;
+        }
+        writer.finish()?;
+    }
+
+    out.flush()?;
+
+    //Verbose details go to stderr so they never mix with the addresses
+    if verbose {
+        eprintln!("Next Cursor:           {}", start + printed);
+    }
+
+    Ok(())
+}
+
+//Reads the -k/--key that orders a shuffle.  Without one a random key is used and shown, so
+//the same order can be asked for again.
+fn select_key(parsed_args: &Args) -> u64 {
+    match parsed_args.parse_value::<u64>("key", &format!("a whole number up to {}", u64::MAX)) {
+        Some(key) => key,
+        None => {
+            let key = match random_key() {
+                Ok(key) => key,
+                Err(error) => {
+                    eprintln!("pmg: unable to choose a shuffle key: {}", error);
+                    process::exit(1);
+                }
+            };
+            eprintln!("Shuffle Key:           {}", key);
+            key
+        }
+    }
+}
+
+//Draws a key from the operating system generator, since a guessable key gives away the order
+fn random_key() -> io::Result<u64> {
+    let mut entropy = EntropySource::Os.open()?;
+    Ok((u64::from(entropy.next_u32()?) << 32) | u64::from(entropy.next_u32()?))
+}
+
+//Reads -o/--start as either an offset or an address inside the prefix.  `position` gives the
+//offset of an address in the order the addresses are printed in.
+fn select_start<F>(parsed_args: &Args, prefix: &MacPrefix, position: F) -> u64
+    where F: Fn(&MacAddress) -> u64
+{
+    let value = match parsed_args.value("start") {
+        Some(value) => value,
+        None => return 0,
+    };
+
+    //A MAC address is tried first, so twelve digits with no separators are an address
+    let start = match value.parse::<MacAddress>() {
+        Ok(ref mac) if prefix.contains(mac) => position(mac),
+        Ok(mac) => usage(&format!("the start address {} is not inside {}", mac, prefix)),
+        Err(_) => match value.parse::<u64>() {
+            Ok(offset) => offset,
+            Err(_) => parsed_args.invalid("start", value, "an offset or a MAC address"),
+        },
+    };
+
+    if start >= prefix.count() {
+        usage(&format!("the start offset {} is past the end of {}, which has {} addresses",
+            start, prefix, prefix.count()));
+    }
+
+    start
+}
+
+//Reads -m/--limit, or None to print to the end of the prefix
+fn select_limit(parsed_args: &Args) -> Option<u64> {
+    parsed_args.parse_value("limit", "a whole number")
+}
+
+//Prints a usage error and stops
+fn usage(message: &str) -> ! {
+    args::usage("pmg range", message)
+}
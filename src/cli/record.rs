@@ -0,0 +1,213 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Structured output shared by every command.  -F/--format json, csv, yaml or tsv prints one
+//record per address, prefix or allocation instead of text for people to read, so scripts
+//never have to scrape labels such as "Private MAC Prefix:".
+
+use pmg::{MacAddress, MacPrefix, Notation};
+use std::io::{self, Write};
+
+use cli::args::Args;
+use cli::json::Json;
+
+//The version of every record: address and prefix records, and the allocation, audit, stats,
+//check and emit records.  Each record has it as its first field.  Fields are only ever added
+//after the last one within a version.  Renaming, removing or reordering a field means a new
+//version.
+pub const SCHEMA_VERSION: u64 = 1;
+
+//The fields of an address or prefix record, in the order they are written
+pub const ADDRESS_FIELDS: &[&str] = &[
+    "schema_version",
+    "kind",
+    "address",
+    "prefix",
+    "prefix_length",
+    "first",
+    "last",
+    "count",
+    "ul_bit",
+    "ig_bit",
+];
+
+//How a command prints its results
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    //The command's own text for people to read, labelled lines or a table
+    Text,
+    //A JSON array of objects
+    Json,
+    //Comma separated values with a heading row, quoted as RFC 4180 describes
+    Csv,
+    //A YAML sequence of mappings
+    Yaml,
+    //Tab separated values with a heading row.  Tabs, newlines and backslashes in a value
+    //are written as \t, \n and \\.
+    Tsv,
+}
+
+//Reads -F/--format.  `text` is what the command calls its own text, such as table.  Another
+//program may be reading the output, so an unknown format is an error rather than falling back
+//to the text.
+pub fn select_format(parsed_args: &Args, text: &str) -> Format {
+    parsed_args.choice("format", &[
+            (text, Format::Text),
+            ("json", Format::Json),
+            ("csv", Format::Csv),
+            ("yaml", Format::Yaml),
+            ("tsv", Format::Tsv),
+        ]).unwrap_or(Format::Text)
+}
+
+//The record of one address.  `prefix` is the prefix the address was drawn from or listed in,
+//and its fields are null when there is none.
+pub fn address_record(mac: &MacAddress, prefix: Option<&MacPrefix>, notation: &Notation) -> Json {
+    record("address", Some(mac), prefix, mac, notation)
+}
+
+//The record of one prefix.  The address field is null.
+pub fn prefix_record(prefix: &MacPrefix, notation: &Notation) -> Json {
+    record("prefix", None, Some(prefix), &prefix.first(), notation)
+}
+
+//Builds a record.  The U/L and I/G bits are read from `bits`, the address itself or the
+//first address of a prefix, since every prefix fixes the whole first octet.
+fn record(kind: &str, mac: Option<&MacAddress>, prefix: Option<&MacPrefix>, bits: &MacAddress, notation: &Notation) -> Json {
+    Json::object(vec![
+        ("schema_version", SCHEMA_VERSION.into()),
+        ("kind", kind.into()),
+        ("address", mac.map(|mac| notation.address(mac)).into()),
+        ("prefix", prefix.map(|prefix| format!("{}/{}", notation.address(&prefix.first()), prefix.prefix_len())).into()),
+        ("prefix_length", prefix.map(|prefix| u64::from(prefix.prefix_len())).into()),
+        ("first", prefix.map(|prefix| notation.address(&prefix.first())).into()),
+        ("last", prefix.map(|prefix| notation.address(&prefix.last())).into()),
+        ("count", prefix.map(MacPrefix::count).into()),
+        ("ul_bit", u64::from(bits.is_local()).into()),
+        ("ig_bit", u64::from(bits.is_multicast()).into()),
+    ])
+}
+
+//Puts schema_version first in a record that is also written elsewhere without it, such as an
+//allocation the HTTP API returns or an audit log entry
+pub fn versioned(record: Json) -> Json {
+    match record {
+        Json::Object(mut fields) => {
+            fields.insert(0, ("schema_version".to_string(), SCHEMA_VERSION.into()));
+            Json::Object(fields)
+        }
+        record => record,
+    }
+}
+
+//Writes records one at a time, so a listing of millions of addresses is never held in memory.
+//Each record is a JSON object.  Its fields named in `fields` are written, in that order, and a
+//field it does not have is written as null.
+pub struct RecordWriter<'a> {
+    out: &'a mut dyn Write,
+    format: Format,
+    fields: &'a [&'a str],
+    written: u64,
+}
+
+impl<'a> RecordWriter<'a> {
+    //Starts the output, writing the heading row of csv and tsv.  The text format has no
+    //records, so it is not accepted.
+    pub fn new(out: &'a mut dyn Write, format: Format, fields: &'a [&'a str]) -> io::Result<RecordWriter<'a>> {
+        assert!(format != Format::Text, "text is written by each command");
+
+        match format {
+            Format::Csv => writeln!(out, "{}", fields.join(","))?,
+            Format::Tsv => writeln!(out, "{}", fields.join("\t"))?,
+            _ => {}
+        }
+
+        Ok(RecordWriter { out, format, fields, written: 0 })
+    }
+
+    pub fn write(&mut self, record: &Json) -> io::Result<()> {
+        let values: Vec<&Json> = self.fields.iter()
+            .map(|field| record.get(field).unwrap_or(&Json::Null))
+            .collect();
+
+        match self.format {
+            Format::Json => {
+                let separator = if self.written == 0 { '[' } else { ',' };
+                let object = Json::Object(self.fields.iter()
+                    .zip(values)
+                    .map(|(field, value)| (field.to_string(), value.clone()))
+                    .collect());
+                write!(self.out, "{}{}", separator, object)?;
+            }
+            Format::Yaml => {
+                //A JSON scalar or object is also a YAML flow scalar or mapping.  Strings are
+                //always quoted so an address such as 02:00:00:00:00:01 is never read as a number.
+                for (i, (field, value)) in self.fields.iter().zip(values).enumerate() {
+                    let indent = if i == 0 { "- " } else { "  " };
+                    writeln!(self.out, "{}{}: {}", indent, field, value)?;
+                }
+            }
+            Format::Csv => {
+                let cells: Vec<String> = values.into_iter().map(|value| csv_cell(&cell(value))).collect();
+                writeln!(self.out, "{}", cells.join(","))?;
+            }
+            Format::Tsv => {
+                let cells: Vec<String> = values.into_iter().map(|value| tsv_cell(&cell(value))).collect();
+                writeln!(self.out, "{}", cells.join("\t"))?;
+            }
+            Format::Text => unreachable!(),
+        }
+
+        self.written += 1;
+        Ok(())
+    }
+
+    //Ends the output.  A JSON or YAML listing with no records is an empty array.
+    pub fn finish(self) -> io::Result<()> {
+        match (self.format, self.written) {
+            (Format::Json, 0) | (Format::Yaml, 0) => writeln!(self.out, "[]"),
+            (Format::Json, _) => writeln!(self.out, "]"),
+            _ => Ok(()),
+        }
+    }
+}
+
+//The text of a csv or tsv cell.  Null is an empty cell, and an object such as the tags of an
+//allocation is written as key=value pairs separated by spaces.
+fn cell(value: &Json) -> String {
+    match *value {
+        Json::Null => String::new(),
+        Json::Bool(value) => value.to_string(),
+        Json::Number(ref value) | Json::String(ref value) => value.clone(),
+        Json::Object(ref fields) => fields.iter()
+            .map(|(key, value)| format!("{}={}", key, cell(value)))
+            .collect::<Vec<String>>()
+            .join(" "),
+        Json::Array(ref values) => values.iter().map(cell).collect::<Vec<String>>().join(" "),
+    }
+}
+
+//Quotes a csv cell that holds a comma, quote or line break, doubling any quotes inside it
+fn csv_cell(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+//Escapes the characters a tsv cell cannot hold
+fn tsv_cell(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r")
+}
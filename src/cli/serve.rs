@@ -0,0 +1,664 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use pmg::{Generator, MacAddress, MacPrefix, Metadata, Notation, Pool, PoolError};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use cli::args::{self, select_notation, Args, Opt};
+use cli::audit;
+use cli::gen::prefix_problem;
+use cli::json::Json;
+use cli::pool::{allocation_json, stats_json};
+use cli::time::{now, parse_duration};
+
+//The options serve takes, as well as args::NOTATION
+const OPTIONS: &[Opt] = &[
+    args::HELP,
+    Opt::value('l', "listen"),
+    Opt::value('u', "socket"),
+    Opt::value('p', "pool"),
+    Opt::flag('v', "verbose"),
+];
+
+//The address listened on without -l/--listen.  Only this host can connect.
+const DEFAULT_LISTEN: &str = "127.0.0.1:8088";
+
+//Limits that keep a slow or hostile client from tying up a thread or memory
+const MAX_HEADER_BYTES: u64 = 16 * 1024;
+const MAX_BODY_BYTES: usize = 64 * 1024;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+//The most connections answered at once.  Any more wait to be accepted until one closes.
+const MAX_CONNECTIONS: usize = 64;
+
+//The most addresses one generate request can ask for
+const MAX_GENERATE: usize = 10000;
+
+const HELP: &str = "Help file for pmg serve, a JSON API for a pool and the generator.
+
+SYNTAX
+    pmg serve [[-l] <address:port>] [[-p] <file>] [-v]
+    pmg serve [-u] <socket> [[-p] <file>] [-v]
+
+OPTIONS
+    -l  --listen        The address and port to listen on.
+                        Defaults:  127.0.0.1:8088
+                        Notes:  The API has no authentication.  Listen on an address
+                                other hosts can reach only on a trusted network.
+    -u  --socket        Listens on a Unix socket at this path instead of a TCP port,
+                        and speaks line-delimited JSON rather than HTTP.
+                        Notes:  Who can connect is decided by the socket's file
+                                permissions.  Each allocation records the user ID of
+                                the client in a uid tag.
+    -p  --pool          The pool file to hand addresses out from.  Without a pool
+                        only generate is available.
+    -v  --verbose       Prints every request to stderr.
+
+    -s, -c, -g and -n change how addresses are written, as in 'pmg gen -h'.
+
+API
+    Request and response bodies are JSON.  Errors are {\"error\": \"message\"}.  Up to 64
+    connections are answered at once, and one that sends nothing for 10 seconds is closed.
+
+    POST    /v1/allocations              Allocates an address.  The body can have
+                                         owner, tags, comment and ttl fields, where
+                                         ttl is seconds or a duration such as \"2h\".
+    GET     /v1/allocations              Lists allocations.  ?owner= and ?tag=key=value
+                                         or ?tag=key narrow the list.
+    GET     /v1/allocations/<mac>        Looks up an allocation.
+    DELETE  /v1/allocations/<mac>        Releases an allocation.
+    POST    /v1/allocations/<mac>/renew  Renews a lease.  The body can have a ttl.
+    GET     /v1/stats                    Counts the allocated and free addresses.
+    GET     /v1/generate                 Generates random addresses.  ?count= asks for
+                                         up to 10000 and ?prefix= keeps them inside a prefix,
+                                         which must be locally administered unicast.
+
+SOCKET PROTOCOL
+    Each request is one line of JSON with an action field, named after the pool actions,
+    and the fields that action needs.  Each is answered by one line, {\"ok\": true,
+    \"result\": ...} or {\"ok\": false, \"status\": 404, \"error\": \"message\"}.  Up to 64
+    clients are answered at once, and a connection that sends nothing for 60 seconds is closed.
+
+    The socket offers what the HTTP API offers: alloc, renew, release, show, find, list,
+    reap, stats and generate.  Run history, verify and fsck with 'pmg pool' on the host
+    that holds the pool.
+
+    {\"action\": \"alloc\", \"owner\": \"vm-42\", \"tags\": {\"env\": \"ci\"}, \"comment\": \"...\", \"ttl\": \"2h\"}
+    {\"action\": \"renew\", \"mac\": \"02:5a:10:00:00:00\", \"ttl\": 3600}
+    {\"action\": \"release\", \"mac\": \"02:5a:10:00:00:00\"}
+    {\"action\": \"show\", \"mac\": \"02:5a:10:00:00:00\"}
+    {\"action\": \"find\", \"owner\": \"vm-42\", \"tags\": {\"env\": \"ci\", \"rack\": null}}
+    {\"action\": \"list\"}, {\"action\": \"reap\"} and {\"action\": \"stats\"}
+    {\"action\": \"generate\", \"count\": 4, \"prefix\": \"02:aa:bb/24\"}
+
+EXAMPLES
+    pmg serve -p lab.pool
+    curl -X POST -d '{\"owner\": \"vm-42\", \"ttl\": \"2h\"}' http://127.0.0.1:8088/v1/allocations
+    curl -X DELETE http://127.0.0.1:8088/v1/allocations/02:5a:10:00:00:00
+    pmg serve -u /run/pmg.sock -p lab.pool
+    echo '{\"action\": \"alloc\", \"owner\": \"vm-42\"}' | nc -U /run/pmg.sock";
+
+//An error returned by an API operation, with the HTTP status it is reported as
+pub struct ApiError {
+    pub status: u16,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(status: u16, message: &str) -> ApiError {
+        ApiError {
+            status,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn bad_request(message: &str) -> ApiError {
+        ApiError::new(400, message)
+    }
+
+    //The body sent to the client
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![("error", self.message.as_str().into())])
+    }
+}
+
+impl From<PoolError> for ApiError {
+    fn from(error: PoolError) -> ApiError {
+        let status = match error {
+            PoolError::NotAllocated(_) => 404,
+            PoolError::Full => 409,
+            PoolError::OutsidePool(_) | PoolError::NoLease(_) => 400,
+            _ => 500,
+        };
+
+        ApiError::new(status, &error.to_string())
+    }
+}
+
+//The operations offered by the API.  Every transport parses its requests into calls on
+//this, so the operations behave the same however they are reached.
+pub struct Service {
+    //The pool addresses are handed out from, if one was given
+    pool: Option<PathBuf>,
+    //How addresses are written in responses
+    notation: Notation,
+}
+
+impl Service {
+    pub fn new(pool: Option<PathBuf>, notation: Notation) -> Service {
+        Service { pool, notation }
+    }
+
+    //Allocates an address for `user`, leased for `ttl` seconds if given.  Each change is
+    //recorded in the audit log under `user`.
+    pub fn allocate(&self, metadata: Metadata, ttl: Option<u64>, user: &str) -> Result<Json, ApiError> {
+        let allocation = audit::update(self.pool()?, user, |pool, changes| {
+            let allocation = pool.allocate(metadata, ttl, changes.now)?;
+            changes.record("alloc", allocation);
+            Ok(allocation.clone())
+        })?;
+        Ok(allocation_json(&allocation, &self.notation))
+    }
+
+    pub fn release(&self, mac: &MacAddress, user: &str) -> Result<Json, ApiError> {
+        let allocation = audit::update(self.pool()?, user, |pool, changes| {
+            let allocation = pool.release(mac)?;
+            changes.record("release", &allocation);
+            Ok(allocation)
+        })?;
+        Ok(allocation_json(&allocation, &self.notation))
+    }
+
+    //Renews a lease, by `ttl` seconds or by the length of the current lease
+    pub fn renew(&self, mac: &MacAddress, ttl: Option<u64>, user: &str) -> Result<Json, ApiError> {
+        let allocation = audit::update(self.pool()?, user, |pool, changes| {
+            let allocation = pool.renew(mac, ttl, changes.now)?;
+            changes.record("renew", allocation);
+            Ok(allocation.clone())
+        })?;
+        Ok(allocation_json(&allocation, &self.notation))
+    }
+
+    //Frees every address whose lease has run out and lists them
+    pub fn reap(&self, user: &str) -> Result<Json, ApiError> {
+        let reaped = audit::update(self.pool()?, user, |pool, changes| {
+            let reaped = pool.reap(changes.now);
+            for allocation in &reaped {
+                changes.record("reap", allocation);
+            }
+            Ok(reaped)
+        })?;
+        Ok(Json::Array(reaped.iter().map(|allocation| allocation_json(allocation, &self.notation)).collect()))
+    }
+
+    pub fn lookup(&self, mac: &MacAddress) -> Result<Json, ApiError> {
+        let pool = Pool::open(self.pool()?)?;
+        match pool.get(mac) {
+            Some(allocation) => Ok(allocation_json(allocation, &self.notation)),
+            None if pool.prefix().contains(mac) => Err(PoolError::NotAllocated(*mac).into()),
+            None => Err(PoolError::OutsidePool(*mac).into()),
+        }
+    }
+
+    //Lists the allocations with `owner`, if given, and every tag.  A tag without a value
+    //matches any value.
+    pub fn find(&self, owner: Option<&str>, tags: &[(String, Option<String>)]) -> Result<Json, ApiError> {
+        let pool = Pool::open(self.pool()?)?;
+        let found = pool.allocations()
+            .filter(|allocation| {
+                let metadata = &allocation.metadata;
+                tags.iter().all(|(key, value)| metadata.has_tag(key, value.as_deref()))
+                    && owner.is_none_or(|owner| metadata.owner.as_deref() == Some(owner))
+            })
+            .map(|allocation| allocation_json(allocation, &self.notation))
+            .collect();
+
+        Ok(Json::Array(found))
+    }
+
+    pub fn stats(&self) -> Result<Json, ApiError> {
+        let pool = Pool::open(self.pool()?)?;
+        Ok(stats_json(&pool, now()))
+    }
+
+    //Generates `count` random addresses, inside `prefix` if one is given
+    pub fn generate(&self, count: usize, prefix: Option<&MacPrefix>) -> Result<Json, ApiError> {
+        if count == 0 || count > MAX_GENERATE {
+            return Err(ApiError::bad_request(&format!("count must be from 1 to {}", MAX_GENERATE)));
+        }
+        //The API has no -f/--force, so a prefix that could clash with real hardware is refused
+        if let Some(prefix) = prefix {
+            if let Some(problem) = prefix_problem(prefix) {
+                return Err(ApiError::bad_request(&format!("prefix {} is {}, not locally administered unicast", prefix, problem)));
+            }
+        }
+
+        let mut generator = Generator::thread();
+        let generated = match prefix {
+            Some(prefix) => generator.addresses_within(prefix, count),
+            None => generator.addresses(count),
+        };
+
+        match generated {
+            Ok(macs) => Ok(Json::object(vec![
+                ("addresses", Json::Array(macs.iter().map(|mac| self.notation.address(mac).into()).collect())),
+            ])),
+            Err(error) => Err(ApiError::bad_request(&error.to_string())),
+        }
+    }
+
+    fn pool(&self) -> Result<&PathBuf, ApiError> {
+        self.pool.as_ref().ok_or_else(|| ApiError::new(503, "the server was started without a pool"))
+    }
+}
+
+//Reads the owner, tags and comment fields of a request
+pub fn metadata_from_json(params: &Json) -> Result<Metadata, ApiError> {
+    let mut metadata = Metadata {
+        owner: optional_string(params, "owner")?,
+        comment: optional_string(params, "comment")?,
+        ..Metadata::default()
+    };
+
+    match params.get("tags") {
+        None | Some(Json::Null) => {}
+        Some(Json::Object(fields)) => {
+            for (key, value) in fields {
+                match value.as_str() {
+                    Some(value) if !key.is_empty() => {
+                        metadata.tags.insert(key.clone(), value.to_string());
+                    }
+                    _ => return Err(ApiError::bad_request("tags must be an object of non-empty keys and string values")),
+                }
+            }
+        }
+        Some(_) => return Err(ApiError::bad_request("tags must be an object of non-empty keys and string values")),
+    }
+
+    Ok(metadata)
+}
+
+//Reads the ttl field of a request, as seconds or as a duration such as "2h"
+pub fn ttl_from_json(params: &Json) -> Result<Option<u64>, ApiError> {
+    let ttl = match params.get("ttl") {
+        None | Some(Json::Null) => return Ok(None),
+        Some(Json::String(value)) => parse_duration(value),
+        Some(value) => value.as_u64().filter(|&ttl| parse_duration(&ttl.to_string()).is_some()),
+    };
+
+    match ttl {
+        Some(ttl) if ttl > 0 => Ok(Some(ttl)),
+        _ => Err(ApiError::bad_request("ttl must be seconds greater than 0 or a duration such as \"2h\"")),
+    }
+}
+
+fn optional_string(params: &Json, key: &str) -> Result<Option<String>, ApiError> {
+    match params.get(key) {
+        None | Some(Json::Null) => Ok(None),
+        Some(Json::String(value)) => Ok(Some(value.clone())),
+        Some(_) => Err(ApiError::bad_request(&format!("{} must be a string", key))),
+    }
+}
+
+pub fn parse_mac(value: &str) -> Result<MacAddress, ApiError> {
+    value.parse().map_err(|error| ApiError::bad_request(&format!("invalid MAC address '{}': {}", value, error)))
+}
+
+//Runs the serve subcommand
+pub fn run(args: &[String]) {
+    let parsed_args = Args::parse("pmg serve", &[OPTIONS, args::NOTATION].concat(), args);
+
+    if parsed_args.flag("help") {
+        println!("{}", HELP);
+        return;
+    }
+    if let Some(value) = parsed_args.positionals.first() {
+        usage(&format!("unexpected value '{}'", value));
+    }
+
+    let listen = parsed_args.value("listen");
+    let socket = parsed_args.value("socket");
+    let pool = parsed_args.value("pool").map(PathBuf::from);
+    let verbose = parsed_args.flag("verbose");
+
+    //A pool that cannot be read is better reported now than on the first request
+    if let Some(ref path) = pool {
+        if let Err(error) = Pool::open(path) {
+            eprintln!("pmg: {}: {}", path.display(), error);
+            process::exit(1);
+        }
+    }
+
+    let service = Arc::new(Service::new(pool, select_notation(&parsed_args)));
+
+    match (listen, socket) {
+        (Some(_), Some(_)) => usage("-l/--listen and -u/--socket cannot be used together"),
+        (None, Some(socket)) => serve_socket(socket, service, verbose),
+        (listen, None) => serve_http(listen.unwrap_or(DEFAULT_LISTEN), service, verbose),
+    }
+}
+
+#[cfg(unix)]
+fn serve_socket(path: &str, service: Arc<Service>, verbose: bool) {
+    ::cli::socket::serve(path, service, verbose);
+}
+
+#[cfg(not(unix))]
+fn serve_socket(_: &str, _: Arc<Service>, _: bool) {
+    eprintln!("pmg: Unix sockets are not supported on this system");
+    process::exit(1);
+}
+
+//Answers HTTP requests on a TCP address until stopped
+fn serve_http(listen: &str, service: Arc<Service>, verbose: bool) {
+    let listener = match TcpListener::bind(listen) {
+        Ok(listener) => listener,
+        Err(error) => {
+            eprintln!("pmg: unable to listen on {}: {}", listen, error);
+            process::exit(1);
+        }
+    };
+
+    //The address is printed once listening, so a port of 0 can be used and then read back
+    match listener.local_addr() {
+        Ok(address) => println!("Listening on http://{}", address),
+        Err(_) => println!("Listening on http://{}", listen),
+    }
+    let _ = io::stdout().flush();
+
+    let handlers = Handlers::new();
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+
+        //Each connection gets its own thread.  Pool changes take the pool's lock, so
+        //requests can be handled side by side.
+        let service = Arc::clone(&service);
+        handlers.spawn(move || handle_connection(stream, &service, verbose));
+    }
+}
+
+//Counts the threads answering connections, so a flood of clients cannot start one thread each
+pub struct Handlers {
+    running: Mutex<usize>,
+    finished: Condvar,
+}
+
+impl Handlers {
+    pub fn new() -> Arc<Handlers> {
+        Arc::new(Handlers { running: Mutex::new(0), finished: Condvar::new() })
+    }
+
+    //Runs `handle` on its own thread, first waiting while MAX_CONNECTIONS are already running
+    pub fn spawn<F: FnOnce() + Send + 'static>(self: &Arc<Self>, handle: F) {
+        let mut running = self.running();
+        while *running >= MAX_CONNECTIONS {
+            running = self.finished.wait(running).unwrap_or_else(|error| error.into_inner());
+        }
+        *running += 1;
+        drop(running);
+
+        let slot = Slot(Arc::clone(self));
+        thread::spawn(move || {
+            let _slot = slot;
+            handle();
+        });
+    }
+
+    //A handler that panicked has still finished, so a poisoned count is used as it is
+    fn running(&self) -> MutexGuard<'_, usize> {
+        self.running.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+//Gives a handler's place back when its thread ends, even if the handler panics
+struct Slot(Arc<Handlers>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        *self.0.running() -= 1;
+        self.0.finished.notify_one();
+    }
+}
+
+//Reads one request, answers it and closes the connection
+fn handle_connection(mut stream: TcpStream, service: &Service, verbose: bool) {
+    let _ = stream.set_read_timeout(Some(TIMEOUT));
+    let _ = stream.set_write_timeout(Some(TIMEOUT));
+
+    //HTTP clients are not authenticated, so changes are recorded against their address
+    let user = stream.peer_addr().map_or("tcp:-".to_string(), |peer| format!("tcp:{}", peer.ip()));
+
+    let (status, body, line) = match read_request(&stream) {
+        Ok(request) => {
+            let (status, body) = match route(service, &request, &user) {
+                Ok((status, body)) => (status, body),
+                Err(error) => (error.status, error.to_json()),
+            };
+            (status, body, format!("{} {}", request.method, request.target))
+        }
+        Err(error) => (error.status, error.to_json(), "-".to_string()),
+    };
+
+    if verbose {
+        let peer = stream.peer_addr().map_or("-".to_string(), |peer| peer.to_string());
+        eprintln!("{} {} {}", peer, line, status);
+    }
+
+    let body = format!("{}\n", body);
+    let response = format!("HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, reason(status), body.len(), body);
+    let _ = stream.write_all(response.as_bytes());
+    let _ = stream.flush();
+}
+
+//The parts of an HTTP request the API uses
+struct Request {
+    method: String,
+    target: String,
+    path: String,
+    //The query string split into decoded names and values, in order
+    query: Vec<(String, String)>,
+    body: String,
+}
+
+impl Request {
+    //The body parsed as JSON.  An empty body is an empty object.
+    fn json(&self) -> Result<Json, ApiError> {
+        if self.body.trim().is_empty() {
+            return Ok(Json::Object(Vec::new()));
+        }
+
+        Json::parse(&self.body).map_err(|error| ApiError::bad_request(&format!("invalid JSON: {}", error)))
+    }
+}
+
+fn read_request(stream: &TcpStream) -> Result<Request, ApiError> {
+    let mut reader = BufReader::new(stream.take(MAX_HEADER_BYTES));
+
+    let mut request_line = String::new();
+    read_line(&mut reader, &mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/1.") => {
+            (method.to_string(), target.to_string())
+        }
+        _ => return Err(ApiError::bad_request("malformed request line")),
+    };
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        read_line(&mut reader, &mut header)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        let (name, value) = match header.find(':') {
+            Some(index) => (header[..index].trim().to_ascii_lowercase(), header[index + 1..].trim()),
+            None => return Err(ApiError::bad_request("malformed header")),
+        };
+        match name.as_str() {
+            "content-length" => {
+                content_length = value.parse().map_err(|_| ApiError::bad_request("invalid Content-Length"))?;
+            }
+            "transfer-encoding" => return Err(ApiError::new(411, "a Content-Length is required")),
+            _ => {}
+        }
+    }
+
+    if content_length > MAX_BODY_BYTES {
+        return Err(ApiError::new(413, "the request body is too large"));
+    }
+
+    //The rest of the request is the body.  The header limit no longer applies to it.
+    let mut body = reader.buffer().to_vec();
+    if body.len() < content_length {
+        let mut rest = vec![0u8; content_length - body.len()];
+        reader.into_inner().into_inner().read_exact(&mut rest)
+            .map_err(|_| ApiError::bad_request("the request body ends early"))?;
+        body.extend_from_slice(&rest);
+    }
+    body.truncate(content_length);
+
+    let body = String::from_utf8(body).map_err(|_| ApiError::bad_request("the request body is not UTF-8"))?;
+
+    let (path, query) = match target.find('?') {
+        Some(index) => (target[..index].to_string(), parse_query(&target[index + 1..])),
+        None => (target.clone(), Vec::new()),
+    };
+
+    Ok(Request { method, target, path, query, body })
+}
+
+fn read_line<R: BufRead>(reader: &mut R, line: &mut String) -> Result<(), ApiError> {
+    match reader.read_line(line) {
+        Ok(0) => Err(ApiError::bad_request("the request ends early")),
+        Ok(_) if !line.ends_with('\n') => Err(ApiError::new(431, "the request headers are too large")),
+        Ok(_) => Ok(()),
+        Err(_) => Err(ApiError::bad_request("the request could not be read")),
+    }
+}
+
+//Calls the operation a request asks for
+//`user` is who the audit log records the changes a request makes as
+fn route(service: &Service, request: &Request, user: &str) -> Result<(u16, Json), ApiError> {
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    let method = request.method.as_str();
+
+    match (method, segments.as_slice()) {
+        ("GET", ["v1", "stats"]) => Ok((200, service.stats()?)),
+        ("GET", ["v1", "generate"]) => {
+            let count = match query_value(&request.query, "count") {
+                Some(count) => count.parse().map_err(|_| ApiError::bad_request("count must be a whole number"))?,
+                None => 1,
+            };
+            let prefix = match query_value(&request.query, "prefix") {
+                Some(prefix) => Some(prefix.parse::<MacPrefix>()
+                    .map_err(|error| ApiError::bad_request(&format!("invalid prefix '{}': {}", prefix, error)))?),
+                None => None,
+            };
+            Ok((200, service.generate(count, prefix.as_ref())?))
+        }
+        ("GET", ["v1", "allocations"]) => {
+            let tags: Vec<(String, Option<String>)> = request.query.iter()
+                .filter(|&(name, _)| name == "tag")
+                .map(|(_, tag)| match tag.find('=') {
+                    Some(index) => (tag[..index].to_string(), Some(tag[index + 1..].to_string())),
+                    None => (tag.clone(), None),
+                })
+                .collect();
+            Ok((200, service.find(query_value(&request.query, "owner"), &tags)?))
+        }
+        ("POST", ["v1", "allocations"]) => {
+            let params = request.json()?;
+            Ok((201, service.allocate(metadata_from_json(&params)?, ttl_from_json(&params)?, user)?))
+        }
+        ("GET", ["v1", "allocations", mac]) => Ok((200, service.lookup(&parse_mac(mac)?)?)),
+        ("DELETE", ["v1", "allocations", mac]) => Ok((200, service.release(&parse_mac(mac)?, user)?)),
+        ("POST", ["v1", "allocations", mac, "renew"]) => {
+            Ok((200, service.renew(&parse_mac(mac)?, ttl_from_json(&request.json()?)?, user)?))
+        }
+        (_, ["v1", "stats"]) | (_, ["v1", "generate"]) | (_, ["v1", "allocations"])
+            | (_, ["v1", "allocations", _]) | (_, ["v1", "allocations", _, "renew"]) => {
+            Err(ApiError::new(405, &format!("{} is not allowed on {}", method, request.path)))
+        }
+        _ => Err(ApiError::new(404, &format!("{} was not found", request.path))),
+    }
+}
+
+fn query_value<'a>(query: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    query.iter().find(|&(key, _)| key == name).map(|(_, value)| value.as_str())
+}
+
+//Splits a query string into names and values, decoding %XX escapes and + for spaces
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.find('=') {
+            Some(index) => (decode(&pair[..index]), decode(&pair[index + 1..])),
+            None => (decode(pair), String::new()),
+        })
+        .collect()
+}
+
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => output.push(b' '),
+            b'%' if i + 2 < bytes.len() && value.is_char_boundary(i + 3) && u8::from_str_radix(&value[i + 1..i + 3], 16).is_ok() => {
+                output.push(u8::from_str_radix(&value[i + 1..i + 3], 16).unwrap_or(b'%'));
+                i += 2;
+            }
+            byte => output.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+//Prints a usage error and stops
+fn usage(message: &str) -> ! {
+    args::usage("pmg serve", message)
+}
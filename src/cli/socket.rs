@@ -0,0 +1,241 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//The Unix socket mode of pmg serve.  Each line a client sends is a JSON request naming an
+//action, and each is answered by one line of JSON.
+
+use pmg::MacPrefix;
+use std::fs;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::process;
+use std::sync::Arc;
+use std::time::Duration;
+
+use cli::json::Json;
+use cli::serve::{metadata_from_json, parse_mac, ttl_from_json, ApiError, Handlers, Service};
+
+//The longest request line accepted
+const MAX_LINE_BYTES: u64 = 64 * 1024;
+
+//How long a response can wait on a client that is not reading
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+//How long a client can keep a connection open between requests
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+//The tag every allocation made over the socket records the client's user ID in
+pub const UID_TAG: &str = "uid";
+
+//Answers requests on a Unix socket at `path` until stopped
+pub fn serve(path: &str, service: Arc<Service>, verbose: bool) {
+    let listener = match bind(path) {
+        Ok(listener) => listener,
+        Err(error) => {
+            eprintln!("pmg: unable to listen on {}: {}", path, error);
+            process::exit(1);
+        }
+    };
+
+    println!("Listening on {}", path);
+    let _ = io::stdout().flush();
+
+    let handlers = Handlers::new();
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+
+        let service = Arc::clone(&service);
+        handlers.spawn(move || handle_connection(stream, &service, verbose));
+    }
+}
+
+//Binds the socket, replacing a socket file left behind by a server that has stopped.  A
+//socket that still answers belongs to a running server and is left alone.
+fn bind(path: &str) -> io::Result<UnixListener> {
+    match UnixListener::bind(path) {
+        Err(ref error) if error.kind() == ErrorKind::AddrInUse => {
+            let is_socket = fs::symlink_metadata(path)?.file_type().is_socket();
+            if !is_socket || UnixStream::connect(path).is_ok() {
+                return Err(io::Error::new(ErrorKind::AddrInUse, "the path is in use"));
+            }
+
+            fs::remove_file(path)?;
+            UnixListener::bind(path)
+        }
+        result => result,
+    }
+}
+
+//Answers requests from one client, one line at a time, until it disconnects or falls idle
+fn handle_connection(stream: UnixStream, service: &Service, verbose: bool) {
+    let _ = stream.set_read_timeout(Some(IDLE_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+
+    //The user ID is read from the kernel, not from the client, so it cannot be forged
+    let uid = match peer_uid(&stream) {
+        Ok(uid) => uid,
+        Err(error) => {
+            let error = ApiError::new(500, &format!("unable to read the peer's credentials: {}", error));
+            let _ = respond(&stream, &Err(error));
+            return;
+        }
+    };
+
+    let mut reader = BufReader::new(&stream);
+    loop {
+        let mut line = String::new();
+        match (&mut reader).take(MAX_LINE_BYTES).read_line(&mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {}
+        }
+
+        if !line.ends_with('\n') && line.len() as u64 == MAX_LINE_BYTES {
+            let _ = respond(&stream, &Err(ApiError::new(413, "the request line is too long")));
+            return;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (action, result) = match Json::parse(&line) {
+            Ok(request) => {
+                let action = request.get("action").and_then(Json::as_str).unwrap_or("-").to_string();
+                (action, dispatch(service, &request, uid))
+            }
+            Err(error) => ("-".to_string(), Err(ApiError::bad_request(&format!("invalid JSON: {}", error)))),
+        };
+
+        if verbose {
+            let status = result.as_ref().map_or_else(|error| error.status, |_| 200);
+            eprintln!("uid={} {} {}", uid, action, status);
+        }
+
+        if respond(&stream, &result).is_err() {
+            return;
+        }
+    }
+}
+
+//Calls the operation a request names in its action field.  The actions match the HTTP API,
+//so history, verify and fsck are left to pmg pool.
+fn dispatch(service: &Service, request: &Json, uid: u32) -> Result<Json, ApiError> {
+    let action = match request.get("action") {
+        Some(action) => action.as_str().ok_or_else(|| ApiError::bad_request("action must be a string"))?,
+        None => return Err(ApiError::bad_request("the request has no action")),
+    };
+    let user = format!("uid:{}", uid);
+
+    match action {
+        "alloc" => {
+            let mut metadata = metadata_from_json(request)?;
+            metadata.tags.insert(UID_TAG.to_string(), uid.to_string());
+            service.allocate(metadata, ttl_from_json(request)?, &user)
+        }
+        "renew" => service.renew(&mac_from_json(request)?, ttl_from_json(request)?, &user),
+        "release" => service.release(&mac_from_json(request)?, &user),
+        "reap" => service.reap(&user),
+        "list" => service.find(None, &[]),
+        "find" => {
+            let owner = match request.get("owner") {
+                None | Some(Json::Null) => None,
+                Some(owner) => Some(owner.as_str().ok_or_else(|| ApiError::bad_request("owner must be a string"))?),
+            };
+            service.find(owner, &tags_from_json(request)?)
+        }
+        "show" => service.lookup(&mac_from_json(request)?),
+        "stats" => service.stats(),
+        "generate" => {
+            let count = match request.get("count") {
+                None | Some(Json::Null) => 1,
+                Some(count) => count.as_u64().ok_or_else(|| ApiError::bad_request("count must be a whole number"))?,
+            };
+            let prefix = match request.get("prefix") {
+                None | Some(Json::Null) => None,
+                Some(Json::String(prefix)) => Some(prefix.parse::<MacPrefix>()
+                    .map_err(|error| ApiError::bad_request(&format!("invalid prefix '{}': {}", prefix, error)))?),
+                Some(_) => return Err(ApiError::bad_request("prefix must be a string")),
+            };
+            service.generate(count.min(usize::MAX as u64) as usize, prefix.as_ref())
+        }
+        _ => Err(ApiError::new(404, &format!("unknown action '{}'", action))),
+    }
+}
+
+//Reads the address a request names in its mac field
+fn mac_from_json(request: &Json) -> Result<::pmg::MacAddress, ApiError> {
+    match request.get("mac").and_then(Json::as_str) {
+        Some(mac) => parse_mac(mac),
+        None => Err(ApiError::bad_request("the request needs a mac")),
+    }
+}
+
+//Reads the tags a find matches.  A null value matches any value of the tag.
+fn tags_from_json(request: &Json) -> Result<Vec<(String, Option<String>)>, ApiError> {
+    match request.get("tags") {
+        None | Some(Json::Null) => Ok(Vec::new()),
+        Some(Json::Object(fields)) => fields.iter()
+            .map(|(key, value)| match value {
+                Json::String(value) => Ok((key.clone(), Some(value.clone()))),
+                Json::Null => Ok((key.clone(), None)),
+                _ => Err(ApiError::bad_request("tags must be an object of string or null values")),
+            })
+            .collect(),
+        Some(_) => Err(ApiError::bad_request("tags must be an object of string or null values")),
+    }
+}
+
+//Writes a response line, {"ok": true, "result": ...} or {"ok": false, "status": ..., "error": ...}
+fn respond(mut stream: &UnixStream, result: &Result<Json, ApiError>) -> io::Result<()> {
+    let response = match *result {
+        Ok(ref value) => Json::object(vec![("ok", true.into()), ("result", value.clone())]),
+        Err(ref error) => Json::object(vec![
+            ("ok", false.into()),
+            ("status", u64::from(error.status).into()),
+            ("error", error.message.as_str().into()),
+        ]),
+    };
+
+    writeln!(stream, "{}", response)
+}
+
+//The user ID of the process on the other end of the socket
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_uid(stream: &UnixStream) -> io::Result<u32> {
+    use libc;
+    use std::mem;
+    use std::os::unix::io::AsRawFd;
+
+    let mut credentials = libc::ucred { pid: 0, uid: 0, gid: 0 };
+    let mut len = mem::size_of::<libc::ucred>() as libc::socklen_t;
+    //SAFETY: the buffer is a ucred and len is its size, as SO_PEERCRED expects
+    let result = unsafe {
+        libc::getsockopt(stream.as_raw_fd(), libc::SOL_SOCKET, libc::SO_PEERCRED,
+            &mut credentials as *mut libc::ucred as *mut libc::c_void, &mut len)
+    };
+
+    if result == 0 {
+        Ok(credentials.uid)
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn peer_uid(_: &UnixStream) -> io::Result<u32> {
+    Err(io::Error::new(ErrorKind::Other, "SO_PEERCRED is not supported on this system"))
+}
@@ -0,0 +1,126 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+//The accepted duration units and their length in seconds
+const UNITS: [(char, u64); 5] = [('s', 1), ('m', 60), ('h', 3600), ('d', 86400), ('w', 604800)];
+
+//The current time in seconds since the Unix epoch
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
+
+//Formats seconds since the Unix epoch as an RFC 3339 time in UTC, 2017-06-01T12:00:00Z
+pub fn format_time(seconds: u64) -> String {
+    let days = (seconds / 86400) as i64;
+    let time = seconds % 86400;
+
+    //Converts days since 1970-01-01 to a civil date, counting in 400 year eras from 0000-03-01
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, time / 3600, time / 60 % 60, time % 60)
+}
+
+//Parses an RFC 3339 time in UTC as written by format_time back into seconds since the Unix
+//epoch.  Returns None for any other text or a time before 1970.
+pub fn parse_time(value: &str) -> Option<u64> {
+    let bytes = value.as_bytes();
+    if bytes.len() != 20 || bytes[4] != b'-' || bytes[7] != b'-' || bytes[10] != b'T'
+        || bytes[13] != b':' || bytes[16] != b':' || bytes[19] != b'Z' {
+        return None;
+    }
+
+    let number = |start: usize, end: usize| -> Option<i64> {
+        let digits = &value[start..end];
+        if digits.bytes().all(|byte| byte.is_ascii_digit()) { digits.parse().ok() } else { None }
+    };
+    let (year, month, day) = (number(0, 4)?, number(5, 7)?, number(8, 10)?);
+    let (hour, minute, second) = (number(11, 13)?, number(14, 16)?, number(17, 19)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+
+    //The reverse of the civil date conversion in format_time
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_index = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let seconds = days * 86400 + hour * 3600 + minute * 60 + second;
+    if seconds < 0 { None } else { Some(seconds as u64) }
+}
+
+//Parses a duration such as 90s, 30m, 2h, 1d or 1h30m into seconds.  A number without a unit
+//is seconds.  Returns None for anything else, or for a duration too long to add to a time.
+pub fn parse_duration(value: &str) -> Option<u64> {
+    if value.is_empty() {
+        return None;
+    }
+
+    let mut total: u64 = 0;
+    let mut number = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = UNITS.iter().find(|&&(name, _)| name == c)?.1;
+        let amount: u64 = number.parse().ok()?;
+        total = total.checked_add(amount.checked_mul(unit)?)?;
+        number.clear();
+    }
+
+    if !number.is_empty() {
+        total = total.checked_add(number.parse().ok()?)?;
+    }
+
+    //Durations are added to the current time, so they are kept well clear of overflowing it
+    if total > u32::MAX as u64 * 60 {
+        None
+    } else {
+        Some(total)
+    }
+}
+
+//Formats seconds in the largest units that divide them, the reverse of parse_duration
+pub fn format_duration(seconds: u64) -> String {
+    if seconds == 0 {
+        return "0s".to_string();
+    }
+
+    let mut output = String::new();
+    let mut remaining = seconds;
+    for &(name, unit) in UNITS.iter().rev() {
+        if remaining >= unit {
+            output.push_str(&format!("{}{}", remaining / unit, name));
+            remaining %= unit;
+        }
+    }
+
+    output
+}
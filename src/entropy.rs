@@ -0,0 +1,140 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use rand::{self, OsRng, Rng, ThreadRng};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::PathBuf;
+
+use rng::SeededRng;
+
+/// A source of random bits for the generator.
+///
+/// Unlike `rand::Rng`, reading from a source can fail.  This lets a source
+/// backed by a file report that it ran out of bytes instead of panicking.
+pub trait Entropy {
+    /// Returns the next 32 random bits.
+    fn next_u32(&mut self) -> io::Result<u32>;
+}
+
+impl Entropy for ThreadRng {
+    fn next_u32(&mut self) -> io::Result<u32> {
+        Ok(Rng::next_u32(self))
+    }
+}
+
+impl Entropy for OsRng {
+    fn next_u32(&mut self) -> io::Result<u32> {
+        Ok(Rng::next_u32(self))
+    }
+}
+
+impl Entropy for SeededRng {
+    fn next_u32(&mut self) -> io::Result<u32> {
+        Ok(Rng::next_u32(self))
+    }
+}
+
+impl<E: Entropy + ?Sized> Entropy for Box<E> {
+    fn next_u32(&mut self) -> io::Result<u32> {
+        (**self).next_u32()
+    }
+}
+
+/// Adapts any `rand::Rng` into an entropy source.
+pub struct RngEntropy<R>(pub R);
+
+impl<R: Rng> Entropy for RngEntropy<R> {
+    fn next_u32(&mut self) -> io::Result<u32> {
+        Ok(self.0.next_u32())
+    }
+}
+
+/// Reads raw random bytes from a file, pipe or any other reader.
+///
+/// Every four bytes are read as one big-endian 32 bit value, so the same
+/// bytes always produce the same addresses.  Once the reader is exhausted
+/// every request fails with `UnexpectedEof`.
+pub struct ReaderEntropy<T> {
+    //The reader the raw bytes are taken from
+    reader: T,
+}
+
+impl<T: Read> ReaderEntropy<T> {
+    /// Creates a source that reads from `reader`.
+    pub fn new(reader: T) -> ReaderEntropy<T> {
+        ReaderEntropy { reader }
+    }
+}
+
+impl<T: Read> Entropy for ReaderEntropy<T> {
+    fn next_u32(&mut self) -> io::Result<u32> {
+        let mut buf = [0u8; 4];
+
+        match self.reader.read_exact(&mut buf) {
+            Ok(()) => Ok(u32::from_be_bytes(buf)),
+            Err(ref error) if error.kind() == io::ErrorKind::UnexpectedEof => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "the entropy source ran out of bytes",
+            )),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// The entropy sources that can be chosen by name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EntropySource {
+    /// The thread-local generator from the `rand` crate.
+    Thread,
+    /// The operating system's cryptographically secure generator.
+    Os,
+    /// The reproducible `SeededRng`.
+    Seed(u64),
+    /// Raw bytes read from a file.
+    File(PathBuf),
+    /// Raw bytes read from standard input.
+    Stdin,
+}
+
+impl EntropySource {
+    /// Opens the source so the generator can draw from it.
+    pub fn open(&self) -> io::Result<Box<dyn Entropy>> {
+        let output: Box<dyn Entropy> = match *self {
+            EntropySource::Thread => Box::new(rand::thread_rng()),
+            EntropySource::Os => Box::new(OsRng::new()?),
+            EntropySource::Seed(seed) => Box::new(SeededRng::new(seed)),
+            EntropySource::File(ref path) => {
+                Box::new(ReaderEntropy::new(BufReader::new(File::open(path)?)))
+            }
+            EntropySource::Stdin => Box::new(ReaderEntropy::new(io::stdin())),
+        };
+
+        Ok(output)
+    }
+}
+
+//Describes the source in the words shown by verbose output
+impl fmt::Display for EntropySource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EntropySource::Thread => write!(f, "thread (thread-local PRNG)"),
+            EntropySource::Os => write!(f, "os (operating system CSPRNG)"),
+            EntropySource::Seed(seed) => write!(f, "seed {} (SplitMix64)", seed),
+            EntropySource::File(ref path) => write!(f, "file {}", path.display()),
+            EntropySource::Stdin => write!(f, "stdin"),
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use mac::{MacAddress, MacPrefix};
+
+//The hexadecimal digits for each case, indexed by the value of a nibble
+const LOWER_DIGITS: &[u8; 16] = b"0123456789abcdef";
+const UPPER_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+/// The case hexadecimal letters are printed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Case {
+    Lower,
+    Upper,
+}
+
+/// Describes how octets are written out as text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Notation {
+    /// The separator placed between groups, or `None` for bare hexadecimal.
+    pub separator: Option<char>,
+    /// The case of the hexadecimal letters.
+    pub case: Case,
+    /// The number of octets written together between separators.
+    pub group: usize,
+}
+
+impl Default for Notation {
+    //Lower case octets separated by a colon, matching the program defaults.
+    fn default() -> Notation {
+        Notation {
+            separator: Some(':'),
+            case: Case::Lower,
+            group: 1,
+        }
+    }
+}
+
+impl Notation {
+    /// Cisco's dotted notation of three 16 bit words, `xxxx.xxxx.xxxx`.
+    pub fn cisco(case: Case) -> Notation {
+        Notation {
+            separator: Some('.'),
+            case,
+            group: 2,
+        }
+    }
+
+    /// Formats any run of octets.  A group that would run past the last
+    /// octet is written short.
+    pub fn octets(&self, octets: &[u8]) -> String {
+        let mut output = String::with_capacity(octets.len() * 3);
+        self.write_octets(octets, &mut output);
+        output
+    }
+
+    /// Appends the formatted octets to `output`.  Reusing one buffer avoids
+    /// an allocation per address when printing millions of them.
+    pub fn write_octets(&self, octets: &[u8], output: &mut String) {
+        let digits = match self.case {
+            Case::Lower => LOWER_DIGITS,
+            Case::Upper => UPPER_DIGITS,
+        };
+        //A group of zero octets would never print a separator
+        let group = self.group.max(1);
+
+        for (i, octet) in octets.iter().enumerate() {
+            //Print the separator between each group skipping the first
+            if i > 0 && i % group == 0 {
+                if let Some(separator) = self.separator {
+                    output.push(separator);
+                }
+            }
+
+            output.push(digits[(octet >> 4) as usize] as char);
+            output.push(digits[(octet & 0x0f) as usize] as char);
+        }
+    }
+
+    /// Formats a full address.
+    pub fn address(&self, mac: &MacAddress) -> String {
+        self.octets(&mac.0)
+    }
+
+    /// Formats only the leading octets of a prefix.
+    pub fn prefix(&self, prefix: &MacPrefix) -> String {
+        self.octets(prefix.octets())
+    }
+}
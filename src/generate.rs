@@ -0,0 +1,365 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use rand::{self, ThreadRng};
+use std::convert::TryFrom;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+use std::io;
+
+use entropy::Entropy;
+use mac::{MacAddress, MacPrefix, GROUP_BIT, LOCAL_BIT};
+use shuffle::Shuffle;
+
+//The most values a batch makes room for before drawing any.  A larger batch grows as it is
+//drawn, so a mistyped count is an error once memory runs out rather than an abort up front.
+const PREALLOCATE: usize = 1 << 16;
+
+/// Generates locally administered addresses and prefixes from any entropy
+/// source.
+///
+/// Every bit is drawn uniformly except the two lowest bits of the first
+/// octet, which mark the address as locally administered (U/L set) and
+/// unicast (I/G clear).  Any of the 2^46 locally administered unicast
+/// addresses can be produced.
+///
+/// The generator only ever asks its source for `next_u32` values and uses
+/// them four bytes at a time in big-endian order, so a deterministic source
+/// such as `SeededRng` always produces the same addresses in the same order.
+///
+/// A batch of distinct values is drawn at random until it is full.  A batch
+/// of more than half the values that can be generated is instead taken from
+/// a `Shuffle` keyed by the entropy source, so it never stalls on duplicates.
+pub struct Generator<E> {
+    //The source of randomness for every digit generated
+    entropy: E,
+}
+
+impl Generator<ThreadRng> {
+    /// Creates a generator backed by the thread-local random number generator.
+    pub fn thread() -> Generator<ThreadRng> {
+        Generator::new(rand::thread_rng())
+    }
+}
+
+impl<E: Entropy> Generator<E> {
+    /// Creates a generator that draws from `entropy`.
+    pub fn new(entropy: E) -> Generator<E> {
+        Generator { entropy }
+    }
+
+    /// Returns the underlying entropy source.
+    pub fn into_inner(self) -> E {
+        self.entropy
+    }
+
+    /// Generates a random locally administered MAC address.
+    pub fn address(&mut self) -> Result<MacAddress, GenerateError> {
+        let octets = self.generate_octets(6)?;
+
+        let mut output = [0u8; 6];
+        output.copy_from_slice(&octets);
+
+        Ok(MacAddress(output))
+    }
+
+    /// Generates a random address inside `prefix`.  Only the bits after the
+    /// prefix are random, the prefix decides the U/L and I/G bits.
+    pub fn address_within(&mut self, prefix: &MacPrefix) -> Result<MacAddress, GenerateError> {
+        let octets = self.generate_within(prefix, 6)?;
+
+        let mut output = [0u8; 6];
+        output.copy_from_slice(&octets);
+
+        Ok(MacAddress(output))
+    }
+
+    /// Generates a random locally administered prefix that leaves `range`
+    /// octets assignable.  Accepted ranges are 1 to 5 octets.
+    pub fn prefix(&mut self, range: usize) -> Result<MacPrefix, GenerateError> {
+        self.prefix_of_len(range_to_len(range)?)
+    }
+
+    /// Generates a random locally administered prefix `len` bits long.
+    pub fn prefix_of_len(&mut self, len: u8) -> Result<MacPrefix, GenerateError> {
+        check_len(len)?;
+
+        let octets = self.generate_octets(len.div_ceil(8) as usize)?;
+
+        Ok(prefix_from_octets(&octets, len))
+    }
+
+    /// Generates a random prefix `len` bits long inside `prefix`.
+    pub fn prefix_within(&mut self, prefix: &MacPrefix, len: u8) -> Result<MacPrefix, GenerateError> {
+        check_within(prefix, len)?;
+
+        let octets = self.generate_within(prefix, len.div_ceil(8) as usize)?;
+
+        Ok(prefix_from_octets(&octets, len))
+    }
+
+    /// Generates `count` distinct random addresses in the order they were drawn.
+    pub fn addresses(&mut self, count: usize) -> Result<Vec<MacAddress>, GenerateError> {
+        self.distinct(count, 46, |generator| generator.address(),
+            |index| MacAddress::try_from(local_unicast(index, 48)).unwrap())
+    }
+
+    /// Generates `count` distinct random addresses inside `prefix`.
+    pub fn addresses_within(&mut self, prefix: &MacPrefix, count: usize) -> Result<Vec<MacAddress>, GenerateError> {
+        let base = u64::from(prefix.first());
+        self.distinct(count, 48 - prefix.prefix_len() as u32,
+            |generator| generator.address_within(prefix),
+            |index| MacAddress::try_from(base + index).unwrap())
+    }
+
+    /// Generates `count` distinct random prefixes that each leave `range`
+    /// octets assignable.
+    pub fn prefixes(&mut self, range: usize, count: usize) -> Result<Vec<MacPrefix>, GenerateError> {
+        self.prefixes_of_len(range_to_len(range)?, count)
+    }
+
+    /// Generates `count` distinct random prefixes that are each `len` bits long.
+    pub fn prefixes_of_len(&mut self, len: u8, count: usize) -> Result<Vec<MacPrefix>, GenerateError> {
+        check_len(len)?;
+
+        self.distinct(count, len as u32 - 2,
+            |generator| generator.prefix_of_len(len),
+            |index| MacPrefix::new(MacAddress::try_from(local_unicast(index, len)).unwrap(), len).unwrap())
+    }
+
+    /// Generates `count` distinct random prefixes `len` bits long inside `prefix`.
+    pub fn prefixes_within(&mut self, prefix: &MacPrefix, len: u8, count: usize) -> Result<Vec<MacPrefix>, GenerateError> {
+        check_within(prefix, len)?;
+
+        let base = u64::from(prefix.first());
+        self.distinct(count, (len - prefix.prefix_len()) as u32,
+            |generator| generator.prefix_within(prefix, len),
+            |index| MacPrefix::new(MacAddress::try_from(base + (index << (48 - len))).unwrap(), len).unwrap())
+    }
+
+    //Draws values until the batch holds the requested number of distinct values, out of the
+    //2^bits that can be generated.  Duplicates are discarded so the batch keeps the order values
+    //were drawn in.  Near the end of the space almost every draw would be a duplicate, so a batch
+    //of more than half the space is instead taken from a shuffled walk over the index of every
+    //value, which nth turns back into the value.
+    fn distinct<T, F, N>(&mut self, count: usize, bits: u32, mut generate: F, nth: N) -> Result<Vec<T>, GenerateError>
+        where T: Copy + Eq + Hash,
+              F: FnMut(&mut Self) -> Result<T, GenerateError>,
+              N: Fn(u64) -> T
+    {
+        let available = 1u64 << bits;
+        if count as u64 > available {
+            return Err(GenerateError::Exhausted { requested: count as u64, available });
+        }
+
+        //A batch that could never fit in memory is refused before anything is drawn, rather than
+        //after filling memory
+        if Vec::<T>::new().try_reserve_exact(count).is_err() {
+            return Err(GenerateError::OutOfMemory(count as u64));
+        }
+        let mut output = Vec::with_capacity(count.min(PREALLOCATE));
+
+        if count as u64 > available / 2 {
+            let key = (u64::from(self.entropy.next_u32()?) << 32) | u64::from(self.entropy.next_u32()?);
+            for index in Shuffle::new(0, bits, key).take(count) {
+                grow(&mut output, count)?;
+                output.push(nth(u64::from(index)));
+            }
+            return Ok(output);
+        }
+
+        let mut seen = HashSet::with_capacity(count.min(PREALLOCATE));
+        while output.len() < count {
+            let value = generate(self)?;
+            if seen.try_reserve(1).is_err() {
+                return Err(GenerateError::OutOfMemory(count as u64));
+            }
+            if seen.insert(value) {
+                grow(&mut output, count)?;
+                output.push(value);
+            }
+        }
+
+        Ok(output)
+    }
+
+    //Generates the requested number of octets.  Every bit is drawn at random except
+    //the two lowest bits of the first octet, which mark the address as locally
+    //administered unicast.
+    //See https://en.wikipedia.org/wiki/MAC_address for details
+    fn generate_octets(&mut self, count: usize) -> io::Result<Vec<u8>> {
+        let mut output = self.draw_octets(count)?;
+
+        output[0] = (output[0] & !GROUP_BIT) | LOCAL_BIT;
+
+        Ok(output)
+    }
+
+    //Generates the requested number of octets with the leading bits copied from the prefix.
+    //Only the bits after the prefix are random.
+    fn generate_within(&mut self, prefix: &MacPrefix, count: usize) -> io::Result<Vec<u8>> {
+        let mut output = self.draw_octets(count)?;
+        let network = prefix.first().octets();
+        let bits = prefix.prefix_len() as usize;
+
+        for (i, octet) in output.iter_mut().enumerate() {
+            //How many bits of this octet belong to the prefix, from none up to all eight
+            let fixed = bits.saturating_sub(i * 8).min(8);
+            let mask = if fixed == 0 { 0 } else { 0xFFu8 << (8 - fixed) };
+
+            *octet = (network[i] & mask) | (*octet & !mask);
+        }
+
+        Ok(output)
+    }
+
+    //Draws the requested number of random octets.  Each draw supplies four octets in
+    //big-endian order.  Any octets left over are discarded.
+    fn draw_octets(&mut self, count: usize) -> io::Result<Vec<u8>> {
+        let mut output = Vec::with_capacity(count + 3);
+
+        while output.len() < count {
+            output.extend_from_slice(&self.entropy.next_u32()?.to_be_bytes());
+        }
+        output.truncate(count);
+
+        Ok(output)
+    }
+}
+
+/// Generates a random locally administered MAC address.
+pub fn generate_address() -> MacAddress {
+    //The thread-local generator never fails to produce bits
+    Generator::thread().address().unwrap()
+}
+
+/// Generates a random locally administered prefix that leaves `range`
+/// octets assignable.  Accepted ranges are 1 to 5 octets.
+pub fn generate_prefix(range: usize) -> Option<MacPrefix> {
+    Generator::thread().prefix(range).ok()
+}
+
+/// Generates `count` distinct random addresses in the order they were drawn.
+pub fn generate_addresses(count: usize) -> Result<Vec<MacAddress>, GenerateError> {
+    Generator::thread().addresses(count)
+}
+
+/// Generates `count` distinct random prefixes that each leave `range` octets
+/// assignable.
+pub fn generate_prefixes(range: usize, count: usize) -> Result<Vec<MacPrefix>, GenerateError> {
+    Generator::thread().prefixes(range, count)
+}
+
+/// The reason an address or batch could not be generated.
+#[derive(Debug)]
+pub enum GenerateError {
+    /// The number of assignable octets is outside 1 to 5.
+    InvalidRange(usize),
+    /// More distinct values were requested than the generator can produce.
+    Exhausted { requested: u64, available: u64 },
+    /// The prefix length is outside 8 to 47 bits.
+    InvalidLength(u8),
+    /// The fixed prefix is as long as or longer than the prefix to generate.
+    PrefixTooLong { prefix_len: u8, len: u8 },
+    /// The entropy source could not supply more random bits.
+    Entropy(io::Error),
+    /// There was not enough memory to hold a batch this many values long.
+    OutOfMemory(u64),
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GenerateError::InvalidRange(range) => {
+                write!(f, "a prefix must leave 1 to 5 assignable octets, not {}", range)
+            }
+            GenerateError::Exhausted { requested, available } => {
+                write!(f, "{} distinct values were requested but only {} can be generated", requested, available)
+            }
+            GenerateError::InvalidLength(len) => {
+                write!(f, "a prefix must be 8 to 47 bits long, not {}", len)
+            }
+            GenerateError::PrefixTooLong { prefix_len, len } => {
+                write!(f, "a /{} prefix leaves no room to generate a /{} prefix inside it", prefix_len, len)
+            }
+            GenerateError::Entropy(ref error) => write!(f, "entropy source failed: {}", error),
+            GenerateError::OutOfMemory(count) => write!(f, "not enough memory for a batch of {} values", count),
+        }
+    }
+}
+
+impl Error for GenerateError {}
+
+impl From<io::Error> for GenerateError {
+    fn from(error: io::Error) -> GenerateError {
+        GenerateError::Entropy(error)
+    }
+}
+
+//Makes room for one more value in a batch of count values, as push would, but returns an error
+//instead of aborting when there is no memory left
+fn grow<T>(output: &mut Vec<T>, count: usize) -> Result<(), GenerateError> {
+    if output.len() == output.capacity() && output.try_reserve(1).is_err() {
+        return Err(GenerateError::OutOfMemory(count as u64));
+    }
+
+    Ok(())
+}
+
+//Converts a number of assignable octets into the length of the prefix in bits
+fn range_to_len(range: usize) -> Result<u8, GenerateError> {
+    if range == 0 || range > 5 {
+        return Err(GenerateError::InvalidRange(range));
+    }
+
+    Ok(((6 - range) * 8) as u8)
+}
+
+//Checks that a prefix length is one MacPrefix accepts
+fn check_len(len: u8) -> Result<(), GenerateError> {
+    if !(MacPrefix::MIN_LEN..=MacPrefix::MAX_LEN).contains(&len) {
+        return Err(GenerateError::InvalidLength(len));
+    }
+
+    Ok(())
+}
+
+//Checks that a prefix len bits long can be generated inside prefix.
+//At least one bit must be left between the fixed prefix and the new prefix length.
+fn check_within(prefix: &MacPrefix, len: u8) -> Result<(), GenerateError> {
+    check_len(len)?;
+    if prefix.prefix_len() >= len {
+        return Err(GenerateError::PrefixTooLong { prefix_len: prefix.prefix_len(), len });
+    }
+
+    Ok(())
+}
+
+//Builds a prefix from generated octets.  Bits after the length are cleared.
+fn prefix_from_octets(octets: &[u8], len: u8) -> MacPrefix {
+    let mut network = [0u8; 6];
+    network[..octets.len()].copy_from_slice(octets);
+
+    MacPrefix::new(MacAddress(network), len).unwrap()
+}
+
+//Returns the first address of the index-th locally administered unicast prefix len bits long.
+//The index fills every bit of the prefix except the U/L and I/G bits, which sit below the top
+//six bits of the first octet.
+fn local_unicast(index: u64, len: u8) -> u64 {
+    let low = len as u32 - 8;
+    ((index >> low) << 42) | (u64::from(LOCAL_BIT) << 40) | ((index & ((1 << low) - 1)) << (48 - len as u32))
+}
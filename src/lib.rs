@@ -0,0 +1,54 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//! Private MAC Generator.  Randomly create locally administered MAC addresses.
+//!
+//! ```
+//! extern crate pmg;
+//!
+//! let mac = pmg::generate_address();
+//! assert_eq!(mac.octets()[0] & 0b10, 0b10);
+//!
+//! let prefix = pmg::generate_prefix(2).unwrap();
+//! assert_eq!(prefix.count(), 65536);
+//!
+//! //The same seed always produces the same addresses
+//! let first = pmg::Generator::new(pmg::SeededRng::new(1234)).addresses(10).unwrap();
+//! let second = pmg::Generator::new(pmg::SeededRng::new(1234)).addresses(10).unwrap();
+//! assert_eq!(first, second);
+//! ```
+
+extern crate rand;
+
+mod classify;
+mod entropy;
+mod format;
+mod generate;
+mod mac;
+mod pool;
+mod prefix;
+mod rng;
+mod shuffle;
+mod template;
+
+pub use classify::SlapQuadrant;
+pub use entropy::{Entropy, EntropySource, ReaderEntropy, RngEntropy};
+pub use format::{Case, Notation};
+pub use generate::{generate_address, generate_addresses, generate_prefix, generate_prefixes, GenerateError, Generator};
+pub use mac::{MacAddress, MacPrefix, ParseMacError};
+pub use pool::{Allocation, Allocations, Backend, Lease, Metadata, Pool, PoolError, PoolLock, Usage};
+pub use prefix::{summarize, Addresses, Subnets};
+pub use rng::SeededRng;
+pub use shuffle::Shuffle;
+pub use template::{Template, TemplateError};
@@ -0,0 +1,364 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use classify::SlapQuadrant;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+//The largest value a 48 bit MAC address can hold.
+const MAX_VALUE: u64 = 0xFFFF_FFFF_FFFF;
+
+//The universal/local bit of the first octet.  Set for a locally administered address.
+pub(crate) const LOCAL_BIT: u8 = 0b10;
+
+//The individual/group bit of the first octet.  Set for a multicast address.
+pub(crate) const GROUP_BIT: u8 = 0b01;
+
+/// A 48-bit MAC address stored as six octets in transmission order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct MacAddress(pub [u8; 6]);
+
+impl MacAddress {
+    /// Creates an address from its six octets.
+    pub fn new(octets: [u8; 6]) -> MacAddress {
+        MacAddress(octets)
+    }
+
+    /// Returns the six octets of the address.
+    pub fn octets(&self) -> [u8; 6] {
+        self.0
+    }
+
+    /// Returns true if the U/L bit marks the address as locally administered.
+    pub fn is_local(&self) -> bool {
+        self.0[0] & LOCAL_BIT != 0
+    }
+
+    /// Returns true if the I/G bit marks the address as a group address.
+    pub fn is_multicast(&self) -> bool {
+        self.0[0] & GROUP_BIT != 0
+    }
+
+    /// Returns true for ff:ff:ff:ff:ff:ff, the address every station receives.
+    pub fn is_broadcast(&self) -> bool {
+        self.0 == [0xff; 6]
+    }
+
+    /// Returns true for 00:00:00:00:00:00, which tools print for a missing
+    /// or unset address.
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0; 6]
+    }
+
+    /// Returns the quadrant of the IEEE 802c Structured Local Address Plan
+    /// the address falls in, or None for a universally administered address.
+    pub fn slap_quadrant(&self) -> Option<SlapQuadrant> {
+        SlapQuadrant::of(self)
+    }
+}
+
+//Prints the address as lower case hexadecimal octets separated by a colon.
+impl fmt::Display for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let o = &self.0;
+        write!(f, "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}", o[0], o[1], o[2], o[3], o[4], o[5])
+    }
+}
+
+//Accepts the colon (02:5a:10:00:00:01, or 2:5a:10:0:0:1 without leading zeros), hyphen
+//(02-5a-10-00-00-01), dotted (025a.1000.0001) and bare (025a10000001) notations in any case.
+impl FromStr for MacAddress {
+    type Err = ParseMacError;
+
+    fn from_str(s: &str) -> Result<MacAddress, ParseMacError> {
+        parse_octets(s.trim()).map(MacAddress)
+    }
+}
+
+impl From<[u8; 6]> for MacAddress {
+    fn from(octets: [u8; 6]) -> MacAddress {
+        MacAddress(octets)
+    }
+}
+
+impl From<MacAddress> for [u8; 6] {
+    fn from(mac: MacAddress) -> [u8; 6] {
+        mac.0
+    }
+}
+
+//The first octet becomes the most significant byte of the 48 bit value.
+impl From<MacAddress> for u64 {
+    fn from(mac: MacAddress) -> u64 {
+        mac.0.iter().fold(0, |value, &octet| (value << 8) | octet as u64)
+    }
+}
+
+//Only values that fit in 48 bits can be converted.
+impl TryFrom<u64> for MacAddress {
+    type Error = ParseMacError;
+
+    fn try_from(value: u64) -> Result<MacAddress, ParseMacError> {
+        if value > MAX_VALUE {
+            return Err(ParseMacError::OutOfRange);
+        }
+
+        let mut octets = [0u8; 6];
+        for (i, octet) in octets.iter_mut().enumerate() {
+            *octet = (value >> (8 * (5 - i))) as u8;
+        }
+
+        Ok(MacAddress(octets))
+    }
+}
+
+/// A block of MAC addresses sharing the same leading bits, written in a
+/// CIDR-like notation such as `02:5a:10:40:00:00/28`.
+///
+/// Prefixes may be 8 to 47 bits long.  The network address always has its
+/// host bits cleared, so two prefixes covering the same block compare equal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MacPrefix {
+    //The first address of the block
+    network: MacAddress,
+    //The number of leading bits shared by every address in the block
+    bits: u8,
+}
+
+impl MacPrefix {
+    /// The shortest prefix accepted.  The first octet holds the U/L and I/G
+    /// bits, so it always belongs to the prefix.
+    pub const MIN_LEN: u8 = 8;
+
+    /// The longest prefix accepted, which leaves two addresses.
+    pub const MAX_LEN: u8 = 47;
+
+    /// Creates the prefix of length `bits` containing `address`.  Any host
+    /// bits set in `address` are cleared.
+    pub fn new(address: MacAddress, bits: u8) -> Option<MacPrefix> {
+        if !(MacPrefix::MIN_LEN..=MacPrefix::MAX_LEN).contains(&bits) {
+            return None;
+        }
+
+        let mask = !((1u64 << (48 - bits as u32)) - 1);
+        let network = MacAddress::try_from(u64::from(address) & mask).unwrap();
+
+        Some(MacPrefix { network, bits })
+    }
+
+    /// Creates a prefix from its leading octets.  Between one and five
+    /// octets are accepted since a prefix must leave room for at least one
+    /// assignable octet.
+    pub fn from_octets(octets: &[u8]) -> Option<MacPrefix> {
+        if octets.is_empty() || octets.len() > 5 {
+            return None;
+        }
+
+        let mut network = [0u8; 6];
+        network[..octets.len()].copy_from_slice(octets);
+
+        MacPrefix::new(MacAddress(network), (octets.len() * 8) as u8)
+    }
+
+    /// Returns the number of leading bits shared by the block.
+    pub fn prefix_len(&self) -> u8 {
+        self.bits
+    }
+
+    /// Returns true if the prefix ends on an octet boundary.
+    pub fn is_octet_aligned(&self) -> bool {
+        self.bits.is_multiple_of(8)
+    }
+
+    /// Returns the octets that hold prefix bits.  When the prefix does not
+    /// end on an octet boundary the last octet also holds cleared host bits.
+    pub fn octets(&self) -> &[u8] {
+        &self.network.0[..self.bits.div_ceil(8) as usize]
+    }
+
+    /// Returns the number of addresses in the block.
+    pub fn count(&self) -> u64 {
+        1 << (48 - self.bits as u32)
+    }
+
+    /// Returns the lowest address in the block.
+    pub fn first(&self) -> MacAddress {
+        self.network
+    }
+
+    /// Returns the highest address in the block.
+    pub fn last(&self) -> MacAddress {
+        let value = u64::from(self.network) | (self.count() - 1);
+        MacAddress::try_from(value).unwrap()
+    }
+
+    /// Returns true if every address in the block is locally administered
+    /// unicast, the only kind pmg is meant to hand out.
+    pub fn is_local_unicast(&self) -> bool {
+        self.network.is_local() && !self.network.is_multicast()
+    }
+}
+
+//Accepts either a full address with a length (02:5a:10:40:00:00/28) or one to five
+//octets in colon (02:5a:10), hyphen (02-5a-10) or bare (025a10) notation, optionally
+//followed by a length (02:5a:10:40/28).  Host bits must be zero.
+impl FromStr for MacPrefix {
+    type Err = ParseMacError;
+
+    fn from_str(s: &str) -> Result<MacPrefix, ParseMacError> {
+        let s = s.trim();
+
+        let (address, bits) = match s.find('/') {
+            Some(index) => {
+                let bits = s[index + 1..].parse::<u8>().map_err(|_| ParseMacError::InvalidPrefixLength)?;
+                (&s[..index], Some(bits))
+            }
+            None => (s, None),
+        };
+
+        //A full address is taken as is, otherwise the octets are padded with zeros
+        let (network, octet_bits) = match address.parse::<MacAddress>() {
+            Ok(mac) => (mac, 48),
+            Err(_) => {
+                let octets = parse_prefix_octets(address)?;
+                let mut network = [0u8; 6];
+                network[..octets.len()].copy_from_slice(&octets);
+                (MacAddress(network), octets.len() as u8 * 8)
+            }
+        };
+
+        //Without a length the prefix is as long as the octets that were typed in
+        let bits = bits.unwrap_or(octet_bits);
+        let prefix = MacPrefix::new(network, bits).ok_or(ParseMacError::InvalidPrefixLength)?;
+
+        if prefix.network != network {
+            return Err(ParseMacError::HostBitsSet);
+        }
+
+        Ok(prefix)
+    }
+}
+
+//Prints the network address followed by the prefix length.
+impl fmt::Display for MacPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.bits)
+    }
+}
+
+//Decodes one to five octets in colon, hyphen or bare notation.
+fn parse_prefix_octets(s: &str) -> Result<Vec<u8>, ParseMacError> {
+    let digits: String = match s.chars().find(|c| !c.is_ascii_hexdigit()) {
+        None => s.to_string(),
+        Some(c @ ':') | Some(c @ '-') => {
+            let groups: Vec<&str> = s.split(c).collect();
+            if groups.iter().any(|g| g.len() != 2) {
+                return Err(ParseMacError::InvalidFormat);
+            }
+            groups.concat()
+        }
+        Some(_) => return Err(ParseMacError::InvalidDigit),
+    };
+
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ParseMacError::InvalidDigit);
+    }
+    if digits.is_empty() || !digits.len().is_multiple_of(2) || digits.len() > 10 {
+        return Err(ParseMacError::InvalidLength);
+    }
+
+    Ok((0..digits.len() / 2)
+        .map(|i| u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16).unwrap())
+        .collect())
+}
+
+/// The reason a value could not be turned into a MAC address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseMacError {
+    /// The text did not contain the expected number of hexadecimal digits.
+    InvalidLength,
+    /// The text contained a character that is not a hexadecimal digit.
+    InvalidDigit,
+    /// The digits were grouped or separated in an unrecognized way.
+    InvalidFormat,
+    /// The integer does not fit in 48 bits.
+    OutOfRange,
+    /// The prefix length is missing, not a number, or outside 8 to 47.
+    InvalidPrefixLength,
+    /// The address has bits set after the prefix length.
+    HostBitsSet,
+}
+
+impl fmt::Display for ParseMacError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match *self {
+            ParseMacError::InvalidLength => "a MAC address must contain 12 hexadecimal digits",
+            ParseMacError::InvalidDigit => "invalid hexadecimal digit in MAC address",
+            ParseMacError::InvalidFormat => "unrecognized MAC address notation",
+            ParseMacError::OutOfRange => "value does not fit in 48 bits",
+            ParseMacError::InvalidPrefixLength => "a prefix length must be between 8 and 47 bits",
+            ParseMacError::HostBitsSet => "the address has bits set after the prefix length",
+        };
+
+        f.write_str(message)
+    }
+}
+
+impl Error for ParseMacError {}
+
+//Splits the text on the separator that was used and checks that the groups
+//have a consistent width before decoding the digits.
+fn parse_octets(s: &str) -> Result<[u8; 6], ParseMacError> {
+    //Work out the notation from the first separator found
+    let separator = s.chars().find(|c| !c.is_ascii_hexdigit());
+
+    let digits: String = match separator {
+        //Bare hexadecimal has no grouping to check
+        None => s.to_string(),
+        //Colon and hyphen notations group the address into six octets.  The leading zero of
+        //an octet may be left out, as BSD and macOS arp print 2:5a:10:0:0:1.
+        Some(c @ ':') | Some(c @ '-') => {
+            let groups: Vec<&str> = s.split(c).collect();
+            if groups.len() != 6 || groups.iter().any(|g| g.is_empty() || g.len() > 2) {
+                return Err(ParseMacError::InvalidFormat);
+            }
+            groups.iter().map(|g| format!("{:0>2}", g)).collect()
+        }
+        //Dotted notation groups the address into three 16 bit words
+        Some('.') => {
+            let groups: Vec<&str> = s.split('.').collect();
+            if groups.len() != 3 || groups.iter().any(|g| g.len() != 4) {
+                return Err(ParseMacError::InvalidFormat);
+            }
+            groups.concat()
+        }
+        Some(_) => return Err(ParseMacError::InvalidDigit),
+    };
+
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ParseMacError::InvalidDigit);
+    }
+    if digits.len() != 12 {
+        return Err(ParseMacError::InvalidLength);
+    }
+
+    let mut octets = [0u8; 6];
+    for (i, octet) in octets.iter_mut().enumerate() {
+        *octet = u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16).unwrap();
+    }
+
+    Ok(octets)
+}
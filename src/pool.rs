@@ -0,0 +1,812 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use rand::{self, Rng};
+use std::collections::btree_map::{self, BTreeMap};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use mac::{MacAddress, MacPrefix};
+
+mod text;
+
+//Every pool file starts with these bytes so other files are never mistaken for a pool
+const MAGIC: &[u8; 8] = b"PMGPOOL\n";
+
+//The version of the file layout written by this release.  Version 1 files, written before
+//leases were added, and version 2 files, written before tags and comments, are still read.
+const VERSION: u16 = 3;
+
+/// An address handed out from a pool.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Allocation {
+    /// The address that was handed out.
+    pub mac: MacAddress,
+    /// When the address was handed out, in seconds since the Unix epoch.
+    pub allocated: u64,
+    /// The lease, or `None` if the address is held until it is released.
+    pub lease: Option<Lease>,
+    /// Who holds the address and why.
+    pub metadata: Metadata,
+}
+
+impl Allocation {
+    /// Returns true if the lease ran out at or before `now`.
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.lease.is_some_and(|lease| lease.expires <= now)
+    }
+}
+
+/// The details recorded with an allocation to say who holds it and why.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// Who or what the address was handed out to, such as `vm-42`.
+    pub owner: Option<String>,
+    /// Free-form `key=value` labels, such as `env=staging`.
+    pub tags: BTreeMap<String, String>,
+    /// A note about the allocation.
+    pub comment: Option<String>,
+}
+
+impl Metadata {
+    /// Returns true if the tag `key` is set, and set to `value` if one is
+    /// given.
+    pub fn has_tag(&self, key: &str, value: Option<&str>) -> bool {
+        match (self.tags.get(key), value) {
+            (Some(tag), Some(value)) => tag == value,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+}
+
+/// How long an address is held before it can be reaped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lease {
+    /// The length of the lease in seconds.  A renewal without a new length
+    /// extends the lease by this much.
+    pub ttl: u64,
+    /// When the lease runs out, in seconds since the Unix epoch.
+    pub expires: u64,
+}
+
+impl Lease {
+    /// A lease of `ttl` seconds starting at `now`.
+    pub fn new(ttl: u64, now: u64) -> Lease {
+        Lease {
+            ttl,
+            expires: now.saturating_add(ttl),
+        }
+    }
+}
+
+/// How many addresses of a pool are in each state at one moment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Usage {
+    /// Every address in the pool.
+    pub capacity: u64,
+    /// The addresses handed out, including those whose lease has run out.
+    pub allocated: u64,
+    /// The allocated addresses whose lease has run out but that have not
+    /// been reaped.
+    pub expired: u64,
+    /// The reaped addresses still waiting out their quarantine.
+    pub quarantined: u64,
+    /// The addresses that can be handed out.
+    pub free: u64,
+}
+
+impl Usage {
+    /// The share of the pool that cannot be handed out, allocated or
+    /// quarantined, as a percentage.
+    pub fn used_percent(&self) -> f64 {
+        if self.capacity == 0 {
+            return 0.0;
+        }
+
+        (self.allocated + self.quarantined) as f64 * 100.0 / self.capacity as f64
+    }
+}
+
+/// How a pool is written to its file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// A compact binary file with a bitmap of the taken addresses.
+    Binary,
+    /// A text file with one line per taken address, sorted by address, that
+    /// can be kept in version control and merged.  Addresses are handed out
+    /// at random rather than lowest first, so pools changed on different
+    /// branches seldom take the same address.
+    Text,
+}
+
+/// A block of addresses that are handed out one at a time and never twice.
+///
+/// Which addresses are taken is kept as a bitmap with one bit per address,
+/// so a /24 pool of 16777216 addresses needs 2 MiB however full it is.  The
+/// details of each allocation are kept alongside it.
+///
+/// An allocation can have a lease.  Once the lease runs out `reap` frees the
+/// address, but it stays in quarantine for the pool's quarantine period
+/// after the lease ran out before it is handed out again.
+///
+/// A pool is saved to a file with `save` and read back with `open`.  A
+/// binary file starts with `PMGPOOL`, a version, the prefix and the
+/// quarantine period, then holds the allocations, the quarantined addresses
+/// and the bitmap.  Numbers are big-endian.  A text file starts with a
+/// `pmg-pool text` line; see `Backend::Text`.  `open` reads either.
+///
+/// Several processes can share one pool file by changing it only through
+/// `update`.  See `update` for how the file is kept consistent.
+#[derive(Clone, Debug)]
+pub struct Pool {
+    //The block addresses are handed out from
+    prefix: MacPrefix,
+    //One bit per address, set when the address is allocated or quarantined.  The first
+    //address is the highest bit of the first byte.
+    bitmap: Vec<u8>,
+    //The details of each allocation, keyed by the offset of the address in the block
+    allocations: BTreeMap<u64, Allocation>,
+    //When each quarantined address can be handed out again, keyed by offset
+    quarantined: BTreeMap<u64, u64>,
+    //How long in seconds a reaped address waits after its lease ran out
+    quarantine: u64,
+    //How the pool is written
+    backend: Backend,
+}
+
+impl Pool {
+    /// The most host bits a pool can have.  A pool must be a /24 or longer.
+    pub const MAX_HOST_BITS: u8 = 24;
+
+    /// Creates an empty pool over `prefix`.
+    pub fn new(prefix: MacPrefix) -> Result<Pool, PoolError> {
+        if 48 - prefix.prefix_len() > Pool::MAX_HOST_BITS {
+            return Err(PoolError::TooLarge(prefix.prefix_len()));
+        }
+
+        Ok(Pool {
+            bitmap: vec![0; bitmap_len(&prefix)],
+            prefix,
+            allocations: BTreeMap::new(),
+            quarantined: BTreeMap::new(),
+            quarantine: 0,
+            backend: Backend::Binary,
+        })
+    }
+
+    /// How the pool is written to its file.
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// Sets how the pool is written.  The next `save` writes the new layout.
+    pub fn set_backend(&mut self, backend: Backend) {
+        self.backend = backend;
+    }
+
+    /// How long in seconds a reaped address is held back after its lease
+    /// ran out.
+    pub fn quarantine(&self) -> u64 {
+        self.quarantine
+    }
+
+    /// Sets how long a reaped address is held back.
+    pub fn set_quarantine(&mut self, seconds: u64) {
+        self.quarantine = seconds;
+    }
+
+    /// The block addresses are handed out from.
+    pub fn prefix(&self) -> MacPrefix {
+        self.prefix
+    }
+
+    /// The number of addresses in the pool.
+    pub fn capacity(&self) -> u64 {
+        self.prefix.count()
+    }
+
+    /// The number of addresses handed out.
+    pub fn allocated(&self) -> u64 {
+        self.allocations.len() as u64
+    }
+
+    /// The number of addresses waiting out their quarantine.
+    pub fn quarantined(&self) -> u64 {
+        self.quarantined.len() as u64
+    }
+
+    /// Returns true if `mac` has been handed out.
+    pub fn is_allocated(&self, mac: &MacAddress) -> bool {
+        self.offset(mac).is_some_and(|offset| self.allocations.contains_key(&offset))
+    }
+
+    /// Returns the allocation of `mac`, if it has been handed out.
+    pub fn get(&self, mac: &MacAddress) -> Option<&Allocation> {
+        self.offset(mac).and_then(|offset| self.allocations.get(&offset))
+    }
+
+    /// Returns every allocation in address order.
+    pub fn allocations(&self) -> Allocations<'_> {
+        Allocations(self.allocations.values())
+    }
+
+    /// Hands out the lowest free address with `metadata`, leased for `ttl`
+    /// seconds or held until released if `ttl` is `None`.  `now` is the time
+    /// in seconds since the Unix epoch.  Addresses whose quarantine is over
+    /// are freed first.
+    pub fn allocate(&mut self, metadata: Metadata, ttl: Option<u64>, now: u64) -> Result<&Allocation, PoolError> {
+        self.end_quarantine(now);
+
+        let free = match self.backend {
+            Backend::Binary => self.free_from(0),
+            //Start at a random address and take the next free one
+            Backend::Text => {
+                let start = rand::thread_rng().gen_range(0, self.capacity());
+                self.free_from(start).or_else(|| self.free_from(0))
+            }
+        };
+        let offset = match free {
+            Some(offset) => offset,
+            None => return Err(PoolError::Full),
+        };
+
+        self.set(offset, true);
+        let allocation = Allocation {
+            mac: self.address(offset),
+            allocated: now,
+            lease: ttl.map(|ttl| Lease::new(ttl, now)),
+            metadata,
+        };
+
+        Ok(self.allocations.entry(offset).or_insert(allocation))
+    }
+
+    /// Returns `mac` to the pool so it can be handed out again.
+    pub fn release(&mut self, mac: &MacAddress) -> Result<Allocation, PoolError> {
+        let offset = match self.offset(mac) {
+            Some(offset) => offset,
+            None => return Err(PoolError::OutsidePool(*mac)),
+        };
+
+        match self.allocations.remove(&offset) {
+            Some(allocation) => {
+                self.set(offset, false);
+                Ok(allocation)
+            }
+            None => Err(PoolError::NotAllocated(*mac)),
+        }
+    }
+
+    /// Extends the lease of `mac` to `ttl` seconds from `now`, or by the
+    /// length of its current lease if `ttl` is `None`.  A lease that has run
+    /// out can be renewed until it is reaped.
+    pub fn renew(&mut self, mac: &MacAddress, ttl: Option<u64>, now: u64) -> Result<&Allocation, PoolError> {
+        let offset = match self.offset(mac) {
+            Some(offset) => offset,
+            None => return Err(PoolError::OutsidePool(*mac)),
+        };
+
+        let allocation = match self.allocations.get_mut(&offset) {
+            Some(allocation) => allocation,
+            None => return Err(PoolError::NotAllocated(*mac)),
+        };
+
+        let ttl = match ttl.or(allocation.lease.map(|lease| lease.ttl)) {
+            Some(ttl) => ttl,
+            None => return Err(PoolError::NoLease(*mac)),
+        };
+        allocation.lease = Some(Lease::new(ttl, now));
+
+        Ok(allocation)
+    }
+
+    /// Frees every allocation whose lease ran out at or before `now` and
+    /// returns them.  Each freed address is quarantined until the pool's
+    /// quarantine period after its lease ran out.  Addresses whose
+    /// quarantine is over are freed as well.
+    pub fn reap(&mut self, now: u64) -> Vec<Allocation> {
+        let expired: Vec<u64> = self.allocations.iter()
+            .filter(|&(_, allocation)| allocation.is_expired(now))
+            .map(|(&offset, _)| offset)
+            .collect();
+
+        let mut reaped = Vec::with_capacity(expired.len());
+        for offset in expired {
+            let allocation = self.allocations.remove(&offset).unwrap();
+            //The bit stays set while the address is quarantined
+            let expires = allocation.lease.map_or(now, |lease| lease.expires);
+            self.quarantined.insert(offset, expires.saturating_add(self.quarantine));
+            reaped.push(allocation);
+        }
+
+        self.end_quarantine(now);
+        reaped
+    }
+
+    /// Returns when `mac` can be handed out again, if it is quarantined.
+    pub fn quarantined_until(&self, mac: &MacAddress) -> Option<u64> {
+        self.offset(mac).and_then(|offset| self.quarantined.get(&offset).cloned())
+    }
+
+    /// Counts the addresses in each state at `now`.  A quarantine that is
+    /// over counts as free even before the next change frees it.
+    pub fn usage(&self, now: u64) -> Usage {
+        let allocated = self.allocated();
+        let expired = self.allocations.values().filter(|allocation| allocation.is_expired(now)).count() as u64;
+        let quarantined = self.quarantined.values().filter(|&&until| until > now).count() as u64;
+
+        Usage {
+            capacity: self.capacity(),
+            allocated,
+            expired,
+            quarantined,
+            free: self.capacity() - allocated - quarantined,
+        }
+    }
+
+    //Frees the quarantined addresses that can be handed out again at `now`
+    fn end_quarantine(&mut self, now: u64) {
+        let ended: Vec<u64> = self.quarantined.iter()
+            .filter(|&(_, &until)| until <= now)
+            .map(|(&offset, _)| offset)
+            .collect();
+
+        for offset in ended {
+            self.quarantined.remove(&offset);
+            self.set(offset, false);
+        }
+    }
+
+    /// Reads a pool from a file written by `save`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Pool, PoolError> {
+        Pool::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Writes the pool to a file, replacing it if it exists.
+    ///
+    /// The pool is written to a temporary file beside `path`, flushed to
+    /// disk and then renamed over `path`.  A crash part way through leaves
+    /// the old file in place, and a reader sees either the old pool or the
+    /// new one, never a mix.  `save` does not take the pool's lock, so two
+    /// processes saving at once can lose a change; use `update` instead.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), PoolError> {
+        let path = path.as_ref();
+        let temporary = sibling(path, ".tmp");
+
+        let file = File::create(&temporary)?;
+        let mut out = BufWriter::new(file);
+        self.write_to(&mut out)?;
+        let file = out.into_inner().map_err(|error| error.into_error())?;
+        file.sync_all()?;
+
+        fs::rename(&temporary, path)?;
+        sync_parent(path)?;
+        Ok(())
+    }
+
+    /// Writes the pool to a new file.  Fails if the file already exists, so
+    /// an existing pool is never wiped out.
+    pub fn create<P: AsRef<Path>>(&self, path: P) -> Result<(), PoolError> {
+        let path = path.as_ref();
+        let _lock = lock(path)?;
+
+        if path.exists() {
+            return Err(PoolError::Io(io::Error::new(io::ErrorKind::AlreadyExists, "the pool file already exists")));
+        }
+
+        self.save(path)
+    }
+
+    /// Takes the lock `update` holds while it changes the pool at `path`,
+    /// waiting for any other process holding it.  Use it to keep something
+    /// else in step with the pool, then `open` and `save` the pool while the
+    /// lock is held.  Calling `update` or `create` while holding the lock
+    /// waits forever.
+    pub fn lock<P: AsRef<Path>>(path: P) -> Result<PoolLock, PoolError> {
+        Ok(lock(path.as_ref())?)
+    }
+
+    /// Reads the pool at `path`, passes it to `change` and saves it if
+    /// `change` succeeds.  Returns whatever `change` returns.
+    ///
+    /// An advisory lock on `path` with `.lock` added is held from before the
+    /// read until after the save, so processes updating the same pool take
+    /// turns and never hand out the same address twice.  The lock file is
+    /// left in place for the next update.
+    pub fn update<P, F, T>(path: P, change: F) -> Result<T, PoolError>
+        where P: AsRef<Path>, F: FnOnce(&mut Pool) -> Result<T, PoolError>
+    {
+        let path = path.as_ref();
+        let _lock = lock(path)?;
+
+        let mut pool = Pool::open(path)?;
+        let output = change(&mut pool)?;
+        pool.save(path)?;
+
+        Ok(output)
+    }
+
+    /// Checks the pool file at `path` and describes every problem found.
+    ///
+    /// A text pool is checked line by line for what a bad merge leaves
+    /// behind: addresses on more than one line, conflict markers, lines that
+    /// cannot be read and addresses outside the prefix.  `open` refuses a
+    /// pool with any of these.  A binary pool has no lines, so it is only
+    /// checked by reading it.
+    pub fn fsck<P: AsRef<Path>>(path: P) -> Result<Vec<String>, PoolError> {
+        let mut reader = BufReader::new(File::open(path)?);
+        if text::is_text(&mut reader)? {
+            return text::fsck(reader);
+        }
+
+        match Pool::read_from(reader) {
+            Ok(_) => Ok(Vec::new()),
+            Err(PoolError::Io(error)) => Err(PoolError::Io(error)),
+            Err(error) => Ok(vec![error.to_string()]),
+        }
+    }
+
+    /// Reads a pool in the layout written by `write_to`, binary or text.
+    pub fn read_from<R: Read>(reader: R) -> Result<Pool, PoolError> {
+        let mut reader = BufReader::new(reader);
+        if text::is_text(&mut reader)? {
+            return text::read(reader);
+        }
+
+        let mut magic = [0u8; 8];
+        read_exact(&mut reader, &mut magic)?;
+        if &magic != MAGIC {
+            return Err(PoolError::Corrupt("not a pool file".to_string()));
+        }
+
+        let version = read_u16(&mut reader)?;
+        if version == 0 || version > VERSION {
+            return Err(PoolError::UnsupportedVersion(version));
+        }
+
+        let mut network = [0u8; 6];
+        read_exact(&mut reader, &mut network)?;
+        let len = read_u8(&mut reader)?;
+        let prefix = match MacPrefix::new(MacAddress(network), len) {
+            Some(prefix) if prefix.first() == MacAddress(network) => prefix,
+            _ => return Err(PoolError::Corrupt(format!("invalid prefix {}/{}", MacAddress(network), len))),
+        };
+        let mut pool = Pool::new(prefix)?;
+        if version >= 2 {
+            pool.quarantine = read_u64(&mut reader)?;
+        }
+
+        let count = read_u32(&mut reader)?;
+        for _ in 0..count {
+            let offset = read_u64(&mut reader)?;
+            let allocated = read_u64(&mut reader)?;
+            //A lease is written as its length and expiry, with a length of 0 for no lease
+            let lease = if version >= 2 {
+                let ttl = read_u64(&mut reader)?;
+                let expires = read_u64(&mut reader)?;
+                if ttl == 0 { None } else { Some(Lease { ttl, expires }) }
+            } else {
+                None
+            };
+            let mut metadata = Metadata {
+                owner: read_string(&mut reader)?,
+                ..Metadata::default()
+            };
+            if version >= 3 {
+                metadata.comment = read_string(&mut reader)?;
+                for _ in 0..read_u16(&mut reader)? {
+                    match (read_string(&mut reader)?, read_string(&mut reader)?) {
+                        (Some(key), Some(value)) => metadata.tags.insert(key, value),
+                        _ => return Err(PoolError::Corrupt(format!("invalid tag at offset {}", offset))),
+                    };
+                }
+            }
+
+            if offset >= pool.capacity() || pool.allocations.contains_key(&offset) {
+                return Err(PoolError::Corrupt(format!("invalid allocation at offset {}", offset)));
+            }
+
+            let mac = pool.address(offset);
+            pool.allocations.insert(offset, Allocation { mac, allocated, lease, metadata });
+        }
+
+        if version >= 2 {
+            let count = read_u32(&mut reader)?;
+            for _ in 0..count {
+                let offset = read_u64(&mut reader)?;
+                let until = read_u64(&mut reader)?;
+
+                if offset >= pool.capacity() || pool.allocations.contains_key(&offset)
+                    || pool.quarantined.insert(offset, until).is_some() {
+                    return Err(PoolError::Corrupt(format!("invalid quarantined address at offset {}", offset)));
+                }
+            }
+        }
+
+        read_exact(&mut reader, &mut pool.bitmap)?;
+
+        //The bitmap must have a bit set for exactly the allocated and quarantined addresses,
+        //otherwise an address could be handed out twice
+        let taken: u64 = pool.bitmap.iter().map(|byte| byte.count_ones() as u64).sum();
+        if taken != pool.allocated() + pool.quarantined()
+            || pool.allocations.keys().chain(pool.quarantined.keys()).any(|&offset| !pool.is_set(offset)) {
+            return Err(PoolError::Corrupt("the bitmap does not match the allocations".to_string()));
+        }
+
+        Ok(pool)
+    }
+
+    /// Writes the pool in the layout described on `Pool`, binary or text
+    /// as `backend` says.
+    pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        if self.backend == Backend::Text {
+            return text::write(self, out);
+        }
+
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_be_bytes())?;
+        out.write_all(&self.prefix.first().octets())?;
+        out.write_all(&[self.prefix.prefix_len()])?;
+        out.write_all(&self.quarantine.to_be_bytes())?;
+
+        out.write_all(&(self.allocations.len() as u32).to_be_bytes())?;
+        for (offset, allocation) in &self.allocations {
+            let lease = allocation.lease.unwrap_or(Lease { ttl: 0, expires: 0 });
+            out.write_all(&offset.to_be_bytes())?;
+            out.write_all(&allocation.allocated.to_be_bytes())?;
+            out.write_all(&lease.ttl.to_be_bytes())?;
+            out.write_all(&lease.expires.to_be_bytes())?;
+            write_string(out, allocation.metadata.owner.as_deref())?;
+            write_string(out, allocation.metadata.comment.as_deref())?;
+            //Only the first 65535 tags are kept, far more than anyone will set
+            out.write_all(&(allocation.metadata.tags.len().min(u16::MAX as usize) as u16).to_be_bytes())?;
+            for (key, value) in allocation.metadata.tags.iter().take(u16::MAX as usize) {
+                write_string(out, Some(key))?;
+                write_string(out, Some(value))?;
+            }
+        }
+
+        out.write_all(&(self.quarantined.len() as u32).to_be_bytes())?;
+        for (offset, until) in &self.quarantined {
+            out.write_all(&offset.to_be_bytes())?;
+            out.write_all(&until.to_be_bytes())?;
+        }
+
+        out.write_all(&self.bitmap)
+    }
+
+    //Returns the offset of the lowest free address at or after `start`.  Whole bytes are
+    //skipped while they are full.
+    fn free_from(&self, start: u64) -> Option<u64> {
+        let mut offset = start;
+        //The last byte of a pool smaller than 8 addresses has bits past the end of the block
+        while offset < self.capacity() {
+            if self.bitmap[(offset / 8) as usize] == 0xff {
+                offset = (offset / 8 + 1) * 8;
+            } else if self.is_set(offset) {
+                offset += 1;
+            } else {
+                return Some(offset);
+            }
+        }
+
+        None
+    }
+
+    //Returns the offset of an address in the block, or None if it is outside it
+    fn offset(&self, mac: &MacAddress) -> Option<u64> {
+        if self.prefix.contains(mac) {
+            Some(u64::from(*mac) - u64::from(self.prefix.first()))
+        } else {
+            None
+        }
+    }
+
+    fn address(&self, offset: u64) -> MacAddress {
+        MacAddress::try_from(u64::from(self.prefix.first()) + offset).unwrap()
+    }
+
+    fn is_set(&self, offset: u64) -> bool {
+        self.bitmap[(offset / 8) as usize] & (0x80 >> (offset % 8)) != 0
+    }
+
+    fn set(&mut self, offset: u64, taken: bool) {
+        let bit = 0x80 >> (offset % 8);
+        let byte = &mut self.bitmap[(offset / 8) as usize];
+        if taken {
+            *byte |= bit;
+        } else {
+            *byte &= !bit;
+        }
+    }
+}
+
+//Takes the lock of the pool at `path`, waiting for any other process holding it.  The lock is
+//released when the returned file is dropped.  The pool file itself cannot be locked since
+//saving replaces it with a new file.
+fn lock(path: &Path) -> io::Result<PoolLock> {
+    let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(sibling(path, ".lock"))?;
+    file.lock()?;
+    Ok(PoolLock { _file: file })
+}
+
+/// The advisory lock on a pool file, released when dropped.  See `Pool::lock`.
+#[derive(Debug)]
+pub struct PoolLock {
+    //The lock is held for as long as the file is open
+    _file: File,
+}
+
+//Returns `path` with `suffix` added to the end of its file name
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+//Flushes the directory holding `path` so a rename into it survives a crash
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if parent != Path::new("") => parent,
+        _ => Path::new("."),
+    };
+    File::open(parent)?.sync_all()
+}
+
+//Other platforms cannot open a directory as a file, and commit renames without it
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+//The number of bytes needed for one bit per address
+fn bitmap_len(prefix: &MacPrefix) -> usize {
+    prefix.count().div_ceil(8) as usize
+}
+
+/// An iterator over the allocations of a pool, in address order.
+#[derive(Clone, Debug)]
+pub struct Allocations<'a>(btree_map::Values<'a, u64, Allocation>);
+
+impl<'a> Iterator for Allocations<'a> {
+    type Item = &'a Allocation;
+
+    fn next(&mut self) -> Option<&'a Allocation> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+//A pool file that ends early is corrupt rather than an ordinary read error
+fn read_exact<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<(), PoolError> {
+    reader.read_exact(buf).map_err(|error| {
+        if error.kind() == io::ErrorKind::UnexpectedEof {
+            PoolError::Corrupt("the file ends early".to_string())
+        } else {
+            PoolError::Io(error)
+        }
+    })
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> Result<u8, PoolError> {
+    let mut buf = [0u8; 1];
+    read_exact(reader, &mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> Result<u16, PoolError> {
+    let mut buf = [0u8; 2];
+    read_exact(reader, &mut buf)?;
+    Ok(u16::from_be_bytes(buf))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, PoolError> {
+    let mut buf = [0u8; 4];
+    read_exact(reader, &mut buf)?;
+    Ok(u32::from_be_bytes(buf))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64, PoolError> {
+    let mut buf = [0u8; 8];
+    read_exact(reader, &mut buf)?;
+    Ok(u64::from_be_bytes(buf))
+}
+
+//Strings are written as a 16 bit length and UTF-8 bytes.  A missing string has the length 0xffff.
+fn read_string<R: Read>(reader: &mut R) -> Result<Option<String>, PoolError> {
+    let len = read_u16(reader)?;
+    if len == u16::MAX {
+        return Ok(None);
+    }
+
+    let mut buf = vec![0u8; len as usize];
+    read_exact(reader, &mut buf)?;
+    match String::from_utf8(buf) {
+        Ok(value) => Ok(Some(value)),
+        Err(_) => Err(PoolError::Corrupt("a string is not valid UTF-8".to_string())),
+    }
+}
+
+fn write_string<W: Write>(out: &mut W, value: Option<&str>) -> io::Result<()> {
+    match value {
+        //Longer strings are cut at the last whole character that fits
+        Some(value) => {
+            let mut end = value.len().min(u16::MAX as usize - 1);
+            while !value.is_char_boundary(end) {
+                end -= 1;
+            }
+            out.write_all(&(end as u16).to_be_bytes())?;
+            out.write_all(&value.as_bytes()[..end])
+        }
+        None => out.write_all(&u16::MAX.to_be_bytes()),
+    }
+}
+
+/// The error returned when a pool cannot be used.
+#[derive(Debug)]
+pub enum PoolError {
+    /// The pool file could not be read or written.
+    Io(io::Error),
+    /// The pool file is damaged or is not a pool file.
+    Corrupt(String),
+    /// The pool file was written by a newer release.
+    UnsupportedVersion(u16),
+    /// The prefix is shorter than a /24.
+    TooLarge(u8),
+    /// Every address has been handed out or is quarantined.
+    Full,
+    /// The address is not inside the pool's prefix.
+    OutsidePool(MacAddress),
+    /// The address has not been handed out.
+    NotAllocated(MacAddress),
+    /// The address has no lease to renew and no new lease length was given.
+    NoLease(MacAddress),
+}
+
+impl fmt::Display for PoolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PoolError::Io(ref error) => write!(f, "{}", error),
+            PoolError::Corrupt(ref reason) => write!(f, "the pool file is corrupt: {}", reason),
+            PoolError::UnsupportedVersion(version) => {
+                write!(f, "the pool file is version {}, this release reads version {}", version, VERSION)
+            }
+            PoolError::TooLarge(len) => {
+                write!(f, "a pool must be a /{} or longer, not a /{}", 48 - Pool::MAX_HOST_BITS, len)
+            }
+            PoolError::Full => write!(f, "every address in the pool is allocated or quarantined"),
+            PoolError::OutsidePool(ref mac) => write!(f, "{} is not inside the pool", mac),
+            PoolError::NotAllocated(ref mac) => write!(f, "{} is not allocated", mac),
+            PoolError::NoLease(ref mac) => write!(f, "{} has no lease to renew and no lease length was given", mac),
+        }
+    }
+}
+
+impl Error for PoolError {}
+
+impl From<io::Error> for PoolError {
+    fn from(error: io::Error) -> PoolError {
+        PoolError::Io(error)
+    }
+}
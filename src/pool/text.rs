@@ -0,0 +1,338 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//The text layout of a pool.  A header names the prefix and quarantine period, then every
+//taken address has a line of its own, sorted by address:
+//
+//    pmg-pool text 1
+//    prefix 02:5a:10:00:00:00/24
+//    quarantine 3600
+//    02:5a:10:00:00:00 allocated=1497000000 owner=vm-42 tag.env=ci
+//    02:5a:10:00:00:01 allocated=1497000000 ttl=7200 expires=1497007200 comment=build%20box
+//    02:5a:10:00:00:02 quarantined until=1497003600
+//
+//Times are seconds since the Unix epoch.  Values have spaces, = and % written as %XX.  The
+//header never changes after a pool is created, so two branches that each take an address
+//only add a line each, and git's union merge keeps both.  Should both take the same address
+//it ends up on two lines, which reading refuses and fsck points out.
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+
+use super::{Allocation, Backend, Lease, Metadata, Pool, PoolError};
+use mac::{MacAddress, MacPrefix};
+
+//The first line of every text pool, followed by the version of the layout
+const MAGIC: &str = "pmg-pool text";
+
+//The version of the text layout written by this release
+const VERSION: u16 = 1;
+
+//A comment written under the first line for whoever opens the file
+const COMMENT: &str = "# One line per taken address.  Merge with git's union driver, then run pmg pool fsck.";
+
+//Merge tools leave these at the start of a line around a conflict
+const CONFLICT_MARKERS: [&str; 4] = ["<<<<<<<", "|||||||", "=======", ">>>>>>>"];
+
+//What a line says about an address
+enum State {
+    Allocated(Allocation),
+    //Quarantined until this time
+    Quarantined(u64),
+}
+
+//A text pool as read, with the line each address was on and every problem found
+struct Parsed {
+    //The empty pool the header describes
+    pool: Pool,
+    entries: Vec<(usize, u64, State)>,
+    problems: Vec<String>,
+}
+
+//Returns true if the file `reader` is at the start of is a text pool.  Nothing is consumed.
+pub fn is_text<R: BufRead>(reader: &mut R) -> io::Result<bool> {
+    Ok(reader.fill_buf()?.starts_with(MAGIC.as_bytes()))
+}
+
+//Reads a text pool, refusing one with any problem fsck would report
+pub fn read<R: BufRead>(reader: R) -> Result<Pool, PoolError> {
+    let parsed = parse(reader)?;
+    if let Some(problem) = parsed.problems.first() {
+        let more = match parsed.problems.len() {
+            1 => String::new(),
+            count => format!(" and {} more problems, see fsck", count - 1),
+        };
+        return Err(PoolError::Corrupt(format!("{}{}", problem, more)));
+    }
+
+    let mut pool = parsed.pool;
+    for (_, offset, state) in parsed.entries {
+        pool.set(offset, true);
+        match state {
+            State::Allocated(allocation) => {
+                pool.allocations.insert(offset, allocation);
+            }
+            State::Quarantined(until) => {
+                pool.quarantined.insert(offset, until);
+            }
+        }
+    }
+
+    Ok(pool)
+}
+
+//Describes every problem in a text pool
+pub fn fsck<R: BufRead>(reader: R) -> Result<Vec<String>, PoolError> {
+    Ok(parse(reader)?.problems)
+}
+
+//Writes a pool in the text layout
+pub fn write<W: Write>(pool: &Pool, out: &mut W) -> io::Result<()> {
+    writeln!(out, "{} {}", MAGIC, VERSION)?;
+    writeln!(out, "{}", COMMENT)?;
+    writeln!(out, "prefix {}", pool.prefix)?;
+    writeln!(out, "quarantine {}", pool.quarantine)?;
+
+    //Allocated and quarantined addresses are written together, in address order
+    let mut lines = BTreeMap::new();
+    for (&offset, allocation) in &pool.allocations {
+        lines.insert(offset, allocation_line(allocation));
+    }
+    for (&offset, &until) in &pool.quarantined {
+        lines.insert(offset, format!("{} quarantined until={}", pool.address(offset), until));
+    }
+
+    for line in lines.values() {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+//The line of an allocation, with its fields in a fixed order so the same allocation is
+//always written the same way
+fn allocation_line(allocation: &Allocation) -> String {
+    let mut line = format!("{} allocated={}", allocation.mac, allocation.allocated);
+    if let Some(lease) = allocation.lease {
+        line.push_str(&format!(" ttl={} expires={}", lease.ttl, lease.expires));
+    }
+
+    let metadata = &allocation.metadata;
+    if let Some(ref owner) = metadata.owner {
+        line.push_str(&format!(" owner={}", escape(owner)));
+    }
+    if let Some(ref comment) = metadata.comment {
+        line.push_str(&format!(" comment={}", escape(comment)));
+    }
+    for (key, value) in &metadata.tags {
+        line.push_str(&format!(" tag.{}={}", escape(key), escape(value)));
+    }
+
+    line
+}
+
+//Reads the header and every line.  A bad header is an error, since nothing after it can be
+//trusted.  A bad line is recorded as a problem and skipped.
+fn parse<R: BufRead>(reader: R) -> Result<Parsed, PoolError> {
+    let mut lines = reader.lines().enumerate().map(|(index, line)| line.map(|line| (index + 1, line)));
+
+    let first = match lines.next() {
+        Some(line) => line?.1,
+        None => return Err(PoolError::Corrupt("the file is empty".to_string())),
+    };
+    let version = first.strip_prefix(MAGIC).and_then(|version| version.trim().parse::<u16>().ok())
+        .ok_or_else(|| PoolError::Corrupt("invalid text pool version".to_string()))?;
+    if version == 0 || version > VERSION {
+        return Err(PoolError::UnsupportedVersion(version));
+    }
+
+    let mut prefix = None;
+    let mut quarantine = None;
+    let mut entries = Vec::new();
+    let mut problems = Vec::new();
+    //The line each offset was first seen on and who held it, to point out duplicates
+    let mut seen: BTreeMap<u64, (usize, String)> = BTreeMap::new();
+
+    for line in lines {
+        let (number, line) = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if CONFLICT_MARKERS.iter().any(|marker| line.starts_with(marker)) {
+            problems.push(format!("line {}: unresolved merge conflict marker", number));
+            continue;
+        }
+
+        let mut fields = line.split(' ').filter(|field| !field.is_empty());
+        let first = fields.next().unwrap_or("");
+
+        //The header lines come once each, before any address
+        if first == "prefix" || first == "quarantine" {
+            let repeated = if first == "prefix" { prefix.is_some() } else { quarantine.is_some() };
+            if repeated || !seen.is_empty() || !problems.is_empty() {
+                return Err(PoolError::Corrupt(format!("line {}: {} must come once, before any address", number, first)));
+            }
+            let value = fields.next().unwrap_or("");
+            if first == "prefix" {
+                prefix = match value.parse::<MacPrefix>() {
+                    Ok(parsed) if parsed.first().to_string() == value.split('/').next().unwrap_or("") => Some(parsed),
+                    _ => return Err(PoolError::Corrupt(format!("line {}: invalid prefix '{}'", number, value))),
+                };
+            } else {
+                quarantine = Some(value.parse()
+                    .map_err(|_| PoolError::Corrupt(format!("line {}: invalid quarantine '{}'", number, value)))?);
+            }
+            continue;
+        }
+
+        let prefix = match prefix {
+            Some(prefix) => prefix,
+            None => return Err(PoolError::Corrupt("the prefix must come before any address".to_string())),
+        };
+
+        let mac = match first.parse::<MacAddress>() {
+            Ok(mac) if prefix.contains(&mac) => mac,
+            Ok(mac) => {
+                problems.push(format!("line {}: {} is not inside {}", number, mac, prefix));
+                continue;
+            }
+            Err(_) => {
+                problems.push(format!("line {}: '{}' is not an address", number, first));
+                continue;
+            }
+        };
+        let offset = u64::from(mac) - u64::from(prefix.first());
+
+        let state = match parse_state(mac, fields) {
+            Ok(state) => state,
+            Err(reason) => {
+                problems.push(format!("line {}: {}", number, reason));
+                continue;
+            }
+        };
+
+        if let Some(&(first_line, ref first_holder)) = seen.get(&offset) {
+            problems.push(format!("line {}: {} ({}) is also on line {} ({})",
+                number, mac, holder(&state), first_line, first_holder));
+            continue;
+        }
+        seen.insert(offset, (number, holder(&state)));
+        entries.push((number, offset, state));
+    }
+
+    let mut pool = match prefix {
+        Some(prefix) => Pool::new(prefix)?,
+        None => return Err(PoolError::Corrupt("the file has no prefix".to_string())),
+    };
+    pool.quarantine = quarantine.unwrap_or(0);
+    pool.backend = Backend::Text;
+
+    Ok(Parsed { pool, entries, problems })
+}
+
+//Says who holds an address, to tell the lines of a duplicate apart
+fn holder(state: &State) -> String {
+    match *state {
+        State::Allocated(ref allocation) => match allocation.metadata.owner {
+            Some(ref owner) => format!("owner {}", owner),
+            None => "no owner".to_string(),
+        },
+        State::Quarantined(_) => "quarantined".to_string(),
+    }
+}
+
+//Reads the fields after the address of a line
+fn parse_state<'a, I: Iterator<Item = &'a str>>(mac: MacAddress, fields: I) -> Result<State, String> {
+    let mut fields = fields.peekable();
+    if fields.peek() == Some(&"quarantined") {
+        fields.next();
+        return match (fields.next().and_then(|field| field.strip_prefix("until=")), fields.next()) {
+            (Some(until), None) => until.parse().map(State::Quarantined).map_err(|_| format!("invalid until '{}'", until)),
+            _ => Err("a quarantined address needs until= and nothing else".to_string()),
+        };
+    }
+
+    let mut allocated = None;
+    let mut ttl = None;
+    let mut expires = None;
+    let mut metadata = Metadata::default();
+
+    for field in fields {
+        let (key, value) = match field.find('=') {
+            Some(index) => (&field[..index], &field[index + 1..]),
+            None => return Err(format!("'{}' is not a key=value field", field)),
+        };
+        let number = || value.parse::<u64>().map_err(|_| format!("invalid {} '{}'", key, value));
+
+        match key {
+            "allocated" => allocated = Some(number()?),
+            "ttl" => ttl = Some(number()?),
+            "expires" => expires = Some(number()?),
+            "owner" => metadata.owner = Some(unescape(value)?),
+            "comment" => metadata.comment = Some(unescape(value)?),
+            _ => match key.strip_prefix("tag.") {
+                Some(tag) if !tag.is_empty() => {
+                    metadata.tags.insert(unescape(tag)?, unescape(value)?);
+                }
+                _ => return Err(format!("unknown field '{}'", key)),
+            },
+        }
+    }
+
+    let lease = match (ttl, expires) {
+        (Some(ttl), Some(expires)) if ttl > 0 => Some(Lease { ttl, expires }),
+        (None, None) => None,
+        _ => return Err("a lease needs both ttl= and expires=".to_string()),
+    };
+
+    match allocated {
+        Some(allocated) => Ok(State::Allocated(Allocation { mac, allocated, lease, metadata })),
+        None => Err("an allocation needs allocated=".to_string()),
+    }
+}
+
+//Writes the characters that separate fields, and any control character, as %XX
+fn escape(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            ' ' | '%' | '=' | '#' => output.push_str(&format!("%{:02X}", c as u32)),
+            c if c.is_control() && (c as u32) < 0x80 => output.push_str(&format!("%{:02X}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output
+}
+
+fn unescape(value: &str) -> Result<String, String> {
+    let bytes = value.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let byte = value.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            match byte {
+                Some(byte) => output.push(byte),
+                None => return Err(format!("invalid escape in '{}'", value)),
+            }
+            i += 3;
+        } else {
+            output.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(output).map_err(|_| format!("'{}' is not valid UTF-8", value))
+}
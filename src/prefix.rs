@@ -0,0 +1,209 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use std::convert::TryFrom;
+
+use mac::{MacAddress, MacPrefix};
+
+impl MacPrefix {
+    /// Returns true if `mac` falls inside the block.
+    pub fn contains(&self, mac: &MacAddress) -> bool {
+        let value = u64::from(*mac);
+        value >= u64::from(self.first()) && value <= u64::from(self.last())
+    }
+
+    /// Returns true if every address of `other` falls inside the block.
+    pub fn contains_prefix(&self, other: &MacPrefix) -> bool {
+        self.prefix_len() <= other.prefix_len() && self.contains(&other.first())
+    }
+
+    /// Returns true if the two blocks share at least one address.  Two
+    /// prefixes either nest or are disjoint, so the overlap is always the
+    /// longer of the two.
+    pub fn overlaps(&self, other: &MacPrefix) -> bool {
+        self.contains_prefix(other) || other.contains_prefix(self)
+    }
+
+    /// Returns the prefix one bit shorter that contains this one, or `None`
+    /// at the minimum length.
+    pub fn supernet(&self) -> Option<MacPrefix> {
+        if self.prefix_len() <= MacPrefix::MIN_LEN {
+            return None;
+        }
+
+        MacPrefix::new(self.first(), self.prefix_len() - 1)
+    }
+
+    /// Divides the block into prefixes `len` bits long, in address order.
+    /// Returns `None` if `len` is not longer than this prefix or is past
+    /// the maximum length.
+    pub fn subnets(&self, len: u8) -> Option<Subnets> {
+        if len <= self.prefix_len() || len > MacPrefix::MAX_LEN {
+            return None;
+        }
+
+        Some(Subnets {
+            next: u64::from(self.first()),
+            step: 1 << (48 - len as u32),
+            remaining: 1 << (len - self.prefix_len()),
+            len,
+        })
+    }
+
+    /// Divides the block into `parts` equal prefixes.  `parts` must be a
+    /// power of two greater than one.
+    pub fn split(&self, parts: u64) -> Option<Subnets> {
+        if parts < 2 || !parts.is_power_of_two() {
+            return None;
+        }
+
+        let extra = parts.trailing_zeros();
+        if self.prefix_len() as u32 + extra > MacPrefix::MAX_LEN as u32 {
+            return None;
+        }
+
+        self.subnets(self.prefix_len() + extra as u8)
+    }
+
+    /// Returns every address in the block, in address order.
+    pub fn addresses(&self) -> Addresses {
+        Addresses {
+            next: u64::from(self.first()),
+            end: u64::from(self.last()) + 1,
+        }
+    }
+}
+
+/// An iterator over the addresses of a prefix.  Skipping ahead with `nth`
+/// or `skip` is immediate, so a window deep inside a large prefix can be
+/// listed without walking the addresses before it.
+#[derive(Clone, Debug)]
+pub struct Addresses {
+    //The next address to produce
+    next: u64,
+    //One past the last address
+    end: u64,
+}
+
+impl Iterator for Addresses {
+    type Item = MacAddress;
+
+    fn next(&mut self) -> Option<MacAddress> {
+        if self.next >= self.end {
+            return None;
+        }
+
+        let mac = MacAddress::try_from(self.next).unwrap();
+        self.next += 1;
+
+        Some(mac)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<MacAddress> {
+        //Addresses are at most 48 bits, so moving past the end cannot overflow
+        self.next = self.end.min(self.next.saturating_add(n as u64));
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.next;
+        (usize::try_from(remaining).unwrap_or(usize::MAX), usize::try_from(remaining).ok())
+    }
+}
+
+/// An iterator over the smaller prefixes a prefix divides into.  The
+/// prefixes are produced one at a time, so even a /8 split into /47s does
+/// not need to be held in memory.
+#[derive(Clone, Debug)]
+pub struct Subnets {
+    //The first address of the next prefix
+    next: u64,
+    //The number of addresses in each prefix
+    step: u64,
+    //The number of prefixes left to produce
+    remaining: u64,
+    //The length of each prefix
+    len: u8,
+}
+
+impl Iterator for Subnets {
+    type Item = MacPrefix;
+
+    fn next(&mut self) -> Option<MacPrefix> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let network = MacAddress::try_from(self.next).unwrap();
+        self.next += self.step;
+        self.remaining -= 1;
+
+        MacPrefix::new(network, self.len)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = usize::try_from(self.remaining).unwrap_or(usize::MAX);
+        (remaining, usize::try_from(self.remaining).ok())
+    }
+}
+
+/// Collapses a list of prefixes into the fewest prefixes covering exactly
+/// the same addresses.  Duplicates and prefixes inside another are dropped,
+/// and neighbouring halves of a shorter prefix are merged into it.  The
+/// result is sorted by address.
+pub fn summarize(prefixes: &[MacPrefix]) -> Vec<MacPrefix> {
+    let mut sorted = prefixes.to_vec();
+    //Sorting by first address and then length puts every containing prefix first
+    sorted.sort();
+
+    //Drop every prefix that is inside the one kept before it
+    let mut output: Vec<MacPrefix> = Vec::with_capacity(sorted.len());
+    for prefix in sorted {
+        if output.last().is_none_or(|last| !last.contains_prefix(&prefix)) {
+            output.push(prefix);
+        }
+    }
+
+    //Merge neighbouring halves until no more can be merged.  A merge can allow the
+    //result to merge with the prefix before it, so each merge is retried against the stack.
+    let mut merged: Vec<MacPrefix> = Vec::with_capacity(output.len());
+    for mut prefix in output {
+        while let Some(&last) = merged.last() {
+            match merge(&last, &prefix) {
+                Some(parent) => {
+                    merged.pop();
+                    prefix = parent;
+                }
+                None => break,
+            }
+        }
+        merged.push(prefix);
+    }
+
+    merged
+}
+
+//Returns the parent prefix when the two prefixes are the lower and upper halves of it
+fn merge(lower: &MacPrefix, upper: &MacPrefix) -> Option<MacPrefix> {
+    if lower.prefix_len() != upper.prefix_len() {
+        return None;
+    }
+
+    let parent = lower.supernet()?;
+    if parent.first() == lower.first() && Some(parent) == upper.supernet() && lower != upper {
+        Some(parent)
+    } else {
+        None
+    }
+}
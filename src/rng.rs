@@ -0,0 +1,55 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use rand::Rng;
+
+//The increment used by SplitMix64, the odd integer closest to 2^64 divided by the golden ratio.
+const GOLDEN_GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// A deterministic random number generator for reproducible output.
+///
+/// This is the SplitMix64 generator, implemented here rather than taken from
+/// the `rand` crate so that its output does not depend on which version of
+/// `rand` pmg was built against.  A given seed produces the same stream on
+/// every platform, and that stream will not change between releases of pmg.
+///
+/// It is not suitable where the addresses must be unpredictable.
+#[derive(Clone, Debug)]
+pub struct SeededRng {
+    //The counter advanced by GOLDEN_GAMMA before every output
+    state: u64,
+}
+
+impl SeededRng {
+    /// Creates a generator from a seed.
+    pub fn new(seed: u64) -> SeededRng {
+        SeededRng { state: seed }
+    }
+}
+
+impl Rng for SeededRng {
+    //The upper half of each 64 bit output is used since it is the better mixed half.
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
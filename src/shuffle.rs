@@ -0,0 +1,172 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use rand::Rng;
+use std::convert::TryFrom;
+
+use mac::{MacAddress, MacPrefix};
+use rng::SeededRng;
+
+//The number of Feistel rounds.  Four rounds are enough for the output to look random, the
+//extra rounds cost little and make the order harder to guess from a few addresses.
+const ROUNDS: usize = 8;
+
+impl MacPrefix {
+    /// Returns every address in the block exactly once, in a pseudorandom
+    /// order chosen by `key`.  See `Shuffle`.
+    pub fn shuffled(&self, key: u64) -> Shuffle {
+        Shuffle::new(u64::from(self.first()), 48 - self.prefix_len() as u32, key)
+    }
+}
+
+/// A keyed walk over every address of a prefix that never repeats.
+///
+/// The host bits of each position are passed through a Feistel network, a
+/// permutation that needs no memory no matter how large the prefix is.  The
+/// same key and prefix always give the same order, on every platform and in
+/// every release.  `cursor` counts the addresses produced so far, and
+/// skipping that many addresses with `nth` or `skip` resumes the walk where
+/// it stopped.
+///
+/// The order is hard to guess but is not a cipher.  It should not be relied
+/// on to keep the next address secret from someone who has seen many.
+#[derive(Clone, Debug)]
+pub struct Shuffle {
+    //The first address of the prefix
+    base: u64,
+    //The number of addresses in the prefix
+    count: u64,
+    //The width in bits of each half of the Feistel network
+    half: u32,
+    //The key mixed into each round
+    round_keys: [u64; ROUNDS],
+    //The position of the next address in the shuffled order
+    next: u64,
+}
+
+impl Shuffle {
+    //Walks the 2^bits values from base.  The generator also walks spaces that are not a single
+    //prefix this way, such as every locally administered prefix of a length, by their index.
+    pub(crate) fn new(base: u64, bits: u32, key: u64) -> Shuffle {
+        //Both halves are the same width, so an odd number of host bits is rounded up
+        //and values past the end of the block are walked past
+        let half = bits.div_ceil(2);
+
+        //Each round takes its own key from the SplitMix64 stream of the shuffle key
+        let mut rng = SeededRng::new(key);
+        let mut round_keys = [0u64; ROUNDS];
+        for round_key in round_keys.iter_mut() {
+            *round_key = rng.next_u64();
+        }
+
+        Shuffle {
+            base,
+            count: 1 << bits,
+            half,
+            round_keys,
+            next: 0,
+        }
+    }
+
+    /// The number of addresses produced so far.
+    pub fn cursor(&self) -> u64 {
+        self.next
+    }
+
+    /// Returns the position of `mac` in the shuffled order, or `None` if it
+    /// is not inside the prefix.
+    pub fn position(&self, mac: &MacAddress) -> Option<u64> {
+        let value = u64::from(*mac);
+        if value < self.base || value - self.base >= self.count {
+            return None;
+        }
+
+        //Walking backwards through the inverse permutation finds the position, skipping
+        //values past the end of the block just as the forward walk does
+        let mut position = self.decrypt(value - self.base);
+        while position >= self.count {
+            position = self.decrypt(position);
+        }
+
+        Some(position)
+    }
+
+    //Returns the host bits of the address at a position in the shuffled order
+    fn permute(&self, position: u64) -> u64 {
+        //Cycle walking keeps the output inside the block.  The network covers at most twice
+        //the block, so on average fewer than two passes are needed.
+        let mut value = self.encrypt(position);
+        while value >= self.count {
+            value = self.encrypt(value);
+        }
+
+        value
+    }
+
+    fn encrypt(&self, value: u64) -> u64 {
+        let mask = (1 << self.half) - 1;
+        let (mut left, mut right) = (value >> self.half, value & mask);
+
+        for &round_key in &self.round_keys {
+            let mixed = left ^ (round(round_key, right) & mask);
+            left = right;
+            right = mixed;
+        }
+
+        (left << self.half) | right
+    }
+
+    fn decrypt(&self, value: u64) -> u64 {
+        let mask = (1 << self.half) - 1;
+        let (mut left, mut right) = (value >> self.half, value & mask);
+
+        for &round_key in self.round_keys.iter().rev() {
+            let mixed = right ^ (round(round_key, left) & mask);
+            right = left;
+            left = mixed;
+        }
+
+        (left << self.half) | right
+    }
+}
+
+//The round function, a single SplitMix64 output seeded by the round key and half block
+fn round(round_key: u64, half: u64) -> u64 {
+    SeededRng::new(round_key ^ half).next_u64()
+}
+
+impl Iterator for Shuffle {
+    type Item = MacAddress;
+
+    fn next(&mut self) -> Option<MacAddress> {
+        if self.next >= self.count {
+            return None;
+        }
+
+        let mac = MacAddress::try_from(self.base + self.permute(self.next)).unwrap();
+        self.next += 1;
+
+        Some(mac)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<MacAddress> {
+        self.next = self.count.min(self.next.saturating_add(n as u64));
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.count - self.next;
+        (usize::try_from(remaining).unwrap_or(usize::MAX), usize::try_from(remaining).ok())
+    }
+}
@@ -0,0 +1,322 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use format::{Case, Notation};
+use mac::{MacAddress, MacPrefix};
+
+//The longest group a modifier can ask for, every octet of an EUI-64
+const MAX_GROUP: usize = 8;
+
+/// A line of output with placeholders, such as `{mac:upper:-} {index} {prefix}`.
+///
+/// A placeholder is a field name in braces, followed by any modifiers, each
+/// after a colon.  `{{` and `}}` are literal braces.
+///
+/// Field | Value
+/// ----- | -----
+/// `mac` | The address, or the first address of a prefix.
+/// `eui64` | The address as an EUI-64, with `ff:fe` inserted in the middle.
+/// `oui`, `nic` | The first and last three octets of the address.
+/// `octet1` to `octet6` | A single octet of the address.
+/// `int` | The address as a decimal integer.
+/// `index` | The position of the address in its batch, counted from 0.
+/// `prefix` | The prefix as its first address and length.
+/// `first`, `last` | The first and last address of the prefix.
+/// `length`, `count` | The length of the prefix in bits and its number of addresses.
+///
+/// The prefix fields are empty when there is no prefix.  Fields written in
+/// hexadecimal take `upper` or `lower` for the case, `none` or a separator
+/// such as `-`, `.` or `colon`, and `g1` to `g8` for the octets written
+/// together between separators.  Anything not given follows the notation
+/// the template is written with.  `eui64` also takes `modified`, which flips
+/// the U/L bit as IPv6 interface identifiers do, and `index` takes the
+/// number to count from.
+///
+/// ```
+/// extern crate pmg;
+///
+/// let template: pmg::Template = "host-{index:1} {mac:upper:none} {eui64:modified:g2}".parse().unwrap();
+/// let mac: pmg::MacAddress = "02:5a:10:00:00:01".parse().unwrap();
+///
+/// let mut line = String::new();
+/// template.write(&mut line, &pmg::Notation::default(), &mac, None, 0);
+/// assert_eq!(line, "host-1 025A10000001 005a:10ff:fe00:0001");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+//A field and the modifiers typed after it
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Placeholder {
+    field: Field,
+    case: Option<Case>,
+    //Some(None) is the none modifier, no separator at all
+    separator: Option<Option<char>>,
+    group: Option<usize>,
+    //Flips the U/L bit of an EUI-64
+    modified: bool,
+    //The number index counts from
+    start: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Mac,
+    Eui64,
+    Oui,
+    Nic,
+    //The octet's position, from 0
+    Octet(usize),
+    Int,
+    Index,
+    Prefix,
+    First,
+    Last,
+    Length,
+    Count,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Field> {
+        let field = match name {
+            "mac" => Field::Mac,
+            "eui64" => Field::Eui64,
+            "oui" => Field::Oui,
+            "nic" => Field::Nic,
+            "int" => Field::Int,
+            "index" => Field::Index,
+            "prefix" => Field::Prefix,
+            "first" => Field::First,
+            "last" => Field::Last,
+            "length" => Field::Length,
+            "count" => Field::Count,
+            _ => match name.strip_prefix("octet").and_then(|n| n.parse::<usize>().ok()) {
+                Some(n @ 1..=6) => Field::Octet(n - 1),
+                _ => return None,
+            },
+        };
+
+        Some(field)
+    }
+
+    //Whether the field is written in hexadecimal, and so takes the notation modifiers
+    fn is_hex(self) -> bool {
+        match self {
+            Field::Mac | Field::Eui64 | Field::Oui | Field::Nic | Field::Octet(_)
+                | Field::Prefix | Field::First | Field::Last => true,
+            Field::Int | Field::Index | Field::Length | Field::Count => false,
+        }
+    }
+}
+
+impl Placeholder {
+    //Parses the text between the braces
+    fn parse(text: &str) -> Result<Placeholder, TemplateError> {
+        let mut words = text.split(':');
+        let name = words.next().unwrap_or("");
+        let field = Field::from_name(name).ok_or_else(|| TemplateError::UnknownField(name.to_string()))?;
+
+        let mut placeholder = Placeholder {
+            field,
+            case: None,
+            separator: None,
+            group: None,
+            modified: false,
+            start: 0,
+        };
+
+        for modifier in words {
+            let bad = || TemplateError::InvalidModifier(name.to_string(), modifier.to_string());
+
+            if field == Field::Index {
+                placeholder.start = modifier.parse().map_err(|_| bad())?;
+                continue;
+            }
+            if !field.is_hex() {
+                return Err(bad());
+            }
+
+            let mut chars = modifier.chars();
+            match (modifier, chars.next(), chars.next()) {
+                ("upper", _, _) => placeholder.case = Some(Case::Upper),
+                ("lower", _, _) => placeholder.case = Some(Case::Lower),
+                ("none", _, _) => placeholder.separator = Some(None),
+                ("colon", _, _) => placeholder.separator = Some(Some(':')),
+                ("modified", _, _) if field == Field::Eui64 => placeholder.modified = true,
+                (_, Some('g'), Some(_)) => match modifier[1..].parse::<usize>() {
+                    Ok(group @ 1..=MAX_GROUP) => placeholder.group = Some(group),
+                    _ => return Err(bad()),
+                },
+                //Any single character that cannot be mistaken for a digit or a modifier
+                (_, Some(c), None) if !c.is_alphanumeric() && c != '{' && c != '}' => {
+                    placeholder.separator = Some(Some(c))
+                }
+                _ => return Err(bad()),
+            }
+        }
+
+        Ok(placeholder)
+    }
+
+    //The notation the field is written in, the template's own with the modifiers applied
+    fn notation(&self, base: &Notation) -> Notation {
+        let separator = self.separator.unwrap_or(base.separator);
+        //Dotted notation groups octets in pairs unless told otherwise, as on the command line
+        let group = match (self.group, self.separator) {
+            (Some(group), _) => group,
+            (None, Some(Some('.'))) => 2,
+            (None, Some(_)) => 1,
+            (None, None) => base.group,
+        };
+
+        Notation {
+            separator,
+            case: self.case.unwrap_or(base.case),
+            group,
+        }
+    }
+
+    fn write(&self, output: &mut String, base: &Notation, mac: &MacAddress, prefix: Option<&MacPrefix>, index: u64) {
+        let notation = self.notation(base);
+        let octets = mac.octets();
+
+        match self.field {
+            Field::Mac => notation.write_octets(&octets, output),
+            Field::Eui64 => {
+                let mut eui = [octets[0], octets[1], octets[2], 0xff, 0xfe, octets[3], octets[4], octets[5]];
+                if self.modified {
+                    eui[0] ^= 0b10;
+                }
+                notation.write_octets(&eui, output);
+            }
+            Field::Oui => notation.write_octets(&octets[..3], output),
+            Field::Nic => notation.write_octets(&octets[3..], output),
+            Field::Octet(n) => notation.write_octets(&octets[n..n + 1], output),
+            Field::Int => output.push_str(&u64::from(*mac).to_string()),
+            Field::Index => output.push_str(&self.start.saturating_add(index).to_string()),
+            Field::Prefix => if let Some(prefix) = prefix {
+                notation.write_octets(&prefix.first().octets(), output);
+                output.push_str(&format!("/{}", prefix.prefix_len()));
+            },
+            Field::First => if let Some(prefix) = prefix {
+                notation.write_octets(&prefix.first().octets(), output);
+            },
+            Field::Last => if let Some(prefix) = prefix {
+                notation.write_octets(&prefix.last().octets(), output);
+            },
+            Field::Length => if let Some(prefix) = prefix {
+                output.push_str(&prefix.prefix_len().to_string());
+            },
+            Field::Count => if let Some(prefix) = prefix {
+                output.push_str(&prefix.count().to_string());
+            },
+        }
+    }
+}
+
+impl Template {
+    /// Appends the template filled in for `mac` to `output`.  `prefix` is the
+    /// prefix the address belongs to, if any, and `index` its position in
+    /// the batch.  Fields without modifiers are written in `notation`.
+    pub fn write(&self, output: &mut String, notation: &Notation, mac: &MacAddress, prefix: Option<&MacPrefix>, index: u64) {
+        for part in &self.parts {
+            match *part {
+                Part::Literal(ref text) => output.push_str(text),
+                Part::Placeholder(ref placeholder) => placeholder.write(output, notation, mac, prefix, index),
+            }
+        }
+    }
+}
+
+impl FromStr for Template {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Template, TemplateError> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.char_indices().peekable();
+
+        while let Some((position, c)) = chars.next() {
+            match c {
+                '{' if chars.peek().map(|&(_, next)| next) == Some('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek().map(|&(_, next)| next) == Some('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let end = s[position..].find('}').ok_or(TemplateError::Unclosed(position))? + position;
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(literal.split_off(0)));
+                    }
+                    parts.push(Part::Placeholder(Placeholder::parse(&s[position + 1..end])?));
+
+                    //Carry on after the closing brace
+                    while chars.peek().is_some_and(|&(next, _)| next <= end) {
+                        chars.next();
+                    }
+                }
+                '}' => return Err(TemplateError::Unopened(position)),
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        Ok(Template { parts })
+    }
+}
+
+/// The reason a template could not be parsed.  Positions are byte offsets
+/// into the template.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TemplateError {
+    /// A `{` has no `}` after it.
+    Unclosed(usize),
+    /// A `}` has no `{` before it.  Write `}}` for a literal brace.
+    Unopened(usize),
+    /// The placeholder names a field that does not exist.
+    UnknownField(String),
+    /// The field does not take the modifier.
+    InvalidModifier(String, String),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TemplateError::Unclosed(position) => write!(f, "the {{ at {} is never closed", position),
+            TemplateError::Unopened(position) => write!(f, "the }} at {} has no {{ before it, write }}}} for a brace", position),
+            TemplateError::UnknownField(ref name) => write!(f, "unknown field '{}'", name),
+            TemplateError::InvalidModifier(ref name, ref modifier) => write!(f, "'{}' cannot be used with {}", modifier, name),
+        }
+    }
+}
+
+impl Error for TemplateError {}
@@ -0,0 +1,99 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Checks how the command line is parsed, and that anything pmg cannot use is an error on stderr
+//rather than a warning mixed in with the output.
+
+mod common;
+
+use common::{assert_usage, pmg, stdout};
+use std::env;
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn every_spelling_of_an_option_is_the_same() {
+    let expected = stdout(&pmg(&["-S", "1", "-u", "-c", "upper"]));
+    assert!(expected.contains(":0A:"), "unexpected output: {}", expected);
+
+    for args in [
+        &["-S1", "-u", "-cupper"][..],
+        &["-uS", "1", "-c", "u"][..],
+        &["--seed=1", "--unique", "--case", "upper"][..],
+        &["-u", "--case=upper", "--seed", "1"][..],
+    ] {
+        assert_eq!(stdout(&pmg(args)), expected, "{:?}", args);
+    }
+
+    //Every case that is accepted is honoured, including the long names
+    assert_eq!(stdout(&pmg(&["-S", "1", "-u", "-c", "lower"])), stdout(&pmg(&["-S", "1", "-u", "-c", "l"])));
+}
+
+#[test]
+fn repeated_options_take_the_last_value() {
+    assert_eq!(stdout(&pmg(&["-S", "1", "-u", "-c", "l", "-c", "u"])), stdout(&pmg(&["-S", "1", "-u", "-c", "u"])));
+    assert_eq!(stdout(&pmg(&["-S", "1", "-u", "-p", "-s", ".", "--separator=-"])), stdout(&pmg(&["-S", "1", "-u", "-p", "-s", "-"])));
+}
+
+#[test]
+fn mistakes_are_usage_errors() {
+    assert_usage(&["-u", "-z"], "unknown option '-z'");
+    assert_usage(&["-u", "--uni"], "unknown option '--uni'");
+    assert_usage(&["-u", "--case=purple"], "invalid -c/--case 'purple': expected l, u, lower or upper");
+    assert_usage(&["-r", "5"], "invalid -r/--range '5'");
+    assert_usage(&["-l", "50"], "invalid -l/--length '50'");
+    assert_usage(&["-u", "-N"], "-N/--count needs a value");
+    assert_usage(&["-u", "-N", "0"], "invalid -N/--count '0': expected a whole number from 1");
+    assert_usage(&["--unique=yes"], "-u/--unique does not take a value");
+    assert_usage(&["-u", "five"], "unknown command 'five'");
+    assert_usage(&["range", "02:5a:10/24", "-L", "-m", "ten"], "invalid -m/--limit 'ten'");
+    assert_usage(&["range", "02:5a:10/24", "-k", "1"], "-k/--key");
+    assert_usage(&["prefix", "split", "02:5a:10/24", "--in", "4"], "unknown option '--in'");
+    assert_usage(&["pool", "list", "lab.pool", "-t", "2h"], "unknown option '-t'");
+}
+
+#[test]
+fn double_dash_ends_the_options() {
+    let dir = env::temp_dir().join(format!("pmg-test-{}-double-dash", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("-lab.pool");
+
+    //The pool file starts with a dash, so it can only be typed after --
+    let name = "-lab.pool";
+    let output = Command::new(env!("CARGO_BIN_EXE_pmg"))
+        .current_dir(&dir)
+        .args(["pool", "init", "-P", "02:5a:10:00:00:00/40", "--", name])
+        .output()
+        .unwrap();
+    stdout(&output);
+    assert!(path.exists(), "{} was not created", path.display());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_pmg"))
+        .current_dir(&dir)
+        .args(["pool", "alloc", "-T", "env=lab", "--tag=team=net", "-T", "rack=4", "--", name])
+        .output()
+        .unwrap();
+    assert_eq!(stdout(&output), "02:5a:10:00:00:00\n");
+
+    //Every -T/--tag was kept, not only the last
+    let output = Command::new(env!("CARGO_BIN_EXE_pmg"))
+        .current_dir(&dir)
+        .args(["pool", "find", "-T", "env", "-T", "team=net", "-T", "rack=4", "-F", "csv", "--", name])
+        .output()
+        .unwrap();
+    assert!(stdout(&output).contains(",env=lab rack=4 team=net,"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,93 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Checks that pmg check classifies addresses, both through the library and on the command line.
+
+extern crate pmg;
+
+mod common;
+
+use common::pmg;
+use pmg::{MacAddress, SlapQuadrant};
+
+fn mac(value: &str) -> MacAddress {
+    value.parse().unwrap()
+}
+
+#[test]
+fn every_common_notation_is_accepted() {
+    let expected = mac("02:5a:10:00:00:01");
+    for value in ["02-5A-10-00-00-01", "025a.1000.0001", "025A10000001", "02:5a:10:0:0:1", "2-5A-10-0-0-1"] {
+        assert_eq!(mac(value), expected, "{}", value);
+    }
+
+    //A leading zero may be left out, but an octet cannot be empty or too long
+    for value in ["02:5a:10::00:01", "02:5a:10:000:00:01", "2:5a:10:0:0", "025a.1000.001"] {
+        assert!(value.parse::<MacAddress>().is_err(), "{} was accepted", value);
+    }
+}
+
+#[test]
+fn quadrants_follow_the_second_hex_digit() {
+    assert_eq!(mac("02:00:00:00:00:01").slap_quadrant(), Some(SlapQuadrant::Aai));
+    assert_eq!(mac("0a:00:00:00:00:01").slap_quadrant(), Some(SlapQuadrant::Eli));
+    assert_eq!(mac("0e:00:00:00:00:01").slap_quadrant(), Some(SlapQuadrant::Sai));
+    assert_eq!(mac("06:00:00:00:00:01").slap_quadrant(), Some(SlapQuadrant::Reserved));
+
+    //The I/G bit does not change the quadrant, and universal addresses have none
+    assert_eq!(mac("0b:00:00:00:00:01").slap_quadrant(), Some(SlapQuadrant::Eli));
+    assert_eq!(mac("08:00:00:00:00:01").slap_quadrant(), None);
+}
+
+#[test]
+fn well_known_addresses_are_named() {
+    assert!(mac("ff:ff:ff:ff:ff:ff").is_broadcast());
+    assert!(mac("00:00:00:00:00:00").is_zero());
+    assert!(mac("01:80:c2:00:00:02").well_known().unwrap().contains("LACP"));
+    assert!(mac("01:80:c2:00:00:05").well_known().unwrap().contains("link local"));
+    assert!(mac("01:00:5e:7f:ff:ff").well_known().unwrap().contains("IPv4 multicast"));
+    assert!(mac("01:00:5e:80:00:00").well_known().is_none());
+    assert!(mac("33:33:00:00:00:01").well_known().unwrap().contains("IPv6 multicast"));
+    assert!(mac("00:00:5e:00:01:0a").well_known().unwrap().contains("VRRP"));
+    assert!(mac("02:5a:10:00:00:01").well_known().is_none());
+}
+
+#[test]
+fn check_reports_the_kind_of_address() {
+    let output = pmg(&["check", "01-80-C2-00-00-0E"]);
+    assert!(output.status.success());
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(report, "Input:                 01-80-C2-00-00-0E
+Address:               01:80:c2:00:00:0e
+Valid:                 yes
+Cast:                  multicast, I/G bit set
+Administration:        universally administered, U/L bit clear
+SLAP Quadrant:         none, universally administered
+Broadcast:             no
+All Zero:              no
+Well Known:            IEEE 802.1 Nearest Bridge, LLDP and PTP peer delay
+
+");
+
+    let report = String::from_utf8(pmg(&["check", "0a5a.1000.0001"]).stdout).unwrap();
+    assert!(report.contains("Cast:                  unicast, I/G bit clear\n"), "{}", report);
+    assert!(report.contains("SLAP Quadrant:         ELI, Extended Local Identifier (xA)\n"), "{}", report);
+
+    let report = String::from_utf8(pmg(&["check", "ff:ff:ff:ff:ff:ff"]).stdout).unwrap();
+    assert!(report.contains("Broadcast:             yes\n"), "{}", report);
+
+    //Any invalid value fails the check, even among valid ones
+    let output = pmg(&["check", "02:5a:10:00:00:01", "02:5a:10:00:00:0g"]);
+    assert_eq!(output.status.code(), Some(1));
+}
@@ -0,0 +1,122 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Checks that every command runs under its own name with its own help, and that pmg without a
+//command still generates addresses.
+
+mod common;
+
+use common::{pmg, pmg_with_input, stdout};
+use std::env;
+use std::fs;
+
+#[test]
+fn pmg_without_a_command_is_gen() {
+    let args = ["-S", "9", "-u", "-N", "3", "-p"];
+    assert_eq!(stdout(&pmg(&args)), stdout(&pmg(&[&["gen"][..], &args[..]].concat())));
+
+    //Usage errors point at the help of gen
+    let output = pmg(&["-u", "--bogus"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Type 'pmg gen -h'"));
+}
+
+#[test]
+fn every_command_has_its_own_help() {
+    assert!(stdout(&pmg(&["help"])).contains("COMMANDS"));
+    assert!(stdout(&pmg(&["-h"])).contains("COMMANDS"));
+
+    for command in ["gen", "check", "convert", "range", "prefix", "pool", "emit", "serve"] {
+        let help = stdout(&pmg(&[command, "-h"]));
+        assert!(help.starts_with(&format!("Help file for pmg {},", command)), "pmg {} -h printed {}", command, help);
+    }
+}
+
+#[test]
+fn check_reports_each_value() {
+    let output = pmg(&["check", "02-5A-10-00-00-01", "025a.1000.0001"]);
+    let report = stdout(&output);
+    assert_eq!(report.matches("Address:               02:5a:10:00:00:01").count(), 2);
+    assert_eq!(report.matches("Valid:                 yes").count(), 2);
+
+    //One bad value fails the whole check, so it can gate a script
+    let output = pmg_with_input(&["check"], "02:5a:10:00:00:01\n# a comment\n02:5a:10:00:00\n");
+    assert_eq!(output.status.code(), Some(1));
+    let report = String::from_utf8_lossy(&output.stdout);
+    assert!(report.contains("Input:                 02:5a:10:00:00\nValid:                 no, "), "unexpected report: {}", report);
+    assert!(!report.contains("comment"));
+}
+
+#[test]
+fn convert_rewrites_every_address() {
+    assert_eq!(stdout(&pmg(&["convert", "025a.1000.00ff", "02:5A:10:00:00:FE", "-s", "-", "-c", "u"])),
+        "02-5A-10-00-00-FF\n02-5A-10-00-00-FE\n");
+    assert_eq!(stdout(&pmg(&["convert", "02-5a-10-00-00-01", "-t", "{index}:{mac:none}"])), "0:025a10000001\n");
+
+    //A bad line is reported and skipped, and the rest are still converted
+    let output = pmg_with_input(&["convert", "-s", "."], "025a10000001\n\nnot-a-mac\n02:5a:10:00:00:02\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "025a.1000.0001\n025a.1000.0002\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid MAC address 'not-a-mac'"));
+}
+
+#[test]
+fn emit_writes_each_allocation() {
+    let dir = env::temp_dir().join(format!("pmg-test-{}-emit", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("lab.pool");
+    let path = path.to_str().unwrap();
+
+    stdout(&pmg(&["pool", "init", path, "-P", "02:5a:10:00:00:00/40"]));
+    stdout(&pmg(&["pool", "alloc", path, "-o", "vm-42"]));
+    stdout(&pmg(&["pool", "alloc", path, "-o", "build farm"]));
+
+    let ethers = stdout(&pmg(&["emit", "ethers", path]));
+    assert_eq!(ethers.lines().skip(1).collect::<Vec<&str>>(), ["02:5a:10:00:00:00 vm-42", "02:5a:10:00:00:01 pmg-025a10000001"]);
+
+    let dnsmasq = stdout(&pmg(&["emit", "dnsmasq", path]));
+    assert!(dnsmasq.starts_with("# Written by pmg emit from "));
+    assert!(dnsmasq.ends_with("dhcp-host=02:5a:10:00:00:00,vm-42\ndhcp-host=02:5a:10:00:00:01,pmg-025a10000001\n"));
+
+    let dhcpd = stdout(&pmg(&["emit", "dhcpd", path]));
+    assert!(dhcpd.contains("host pmg-025a10000000 {\n  hardware ethernet 02:5a:10:00:00:00;\n  option host-name \"vm-42\";\n}\n"));
+
+    //Records carry the host name the target would be given, for scripts
+    assert_eq!(stdout(&pmg(&["emit", "ethers", path, "-F", "csv"])), "schema_version,target,mac,host_name,owner\n\
+        1,ethers,02:5a:10:00:00:00,vm-42,vm-42\n\
+        1,ethers,02:5a:10:00:00:01,pmg-025a10000001,build farm\n");
+
+    assert_eq!(pmg(&["emit", "hosts", path]).status.code(), Some(2));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn check_and_emit_print_records() {
+    let output = pmg(&["check", "-F", "json", "02:00:00:00:00:01"]);
+    assert_eq!(stdout(&output), "[{\"schema_version\":1,\"input\":\"02:00:00:00:00:01\",\"valid\":true,\"error\":null,\
+        \"address\":\"02:00:00:00:00:01\",\"ul_bit\":1,\"ig_bit\":0,\"slap_quadrant\":\"AAI\",\"broadcast\":false,\
+        \"all_zero\":false,\"well_known\":null}]\n");
+
+    //A value that is not an address is still a record, and still fails the check
+    let output = pmg(&["check", "-F", "tsv", "ff-ff-ff-ff-ff-ff", "nope"]);
+    assert_eq!(output.status.code(), Some(1));
+    let records = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = records.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[1], "1\tff-ff-ff-ff-ff-ff\ttrue\t\tff:ff:ff:ff:ff:ff\t1\t1\tSAI\ttrue\tfalse\tBroadcast");
+    assert!(lines[2].starts_with("1\tnope\tfalse\t"), "unexpected record: {}", lines[2]);
+
+    assert_eq!(pmg(&["check", "-F", "xml", "02:00:00:00:00:01"]).status.code(), Some(2));
+}
@@ -0,0 +1,51 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Helpers shared by the tests that run the pmg binary.  Each test file uses only some of them.
+#![allow(dead_code)]
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+pub fn pmg(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_pmg")).args(args).output().unwrap()
+}
+
+//Runs pmg with `input` on standard input
+pub fn pmg_with_input(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_pmg"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+//Returns the standard output of a run that must have succeeded
+pub fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "pmg failed: {}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+//Checks the command line was refused with a usage error naming `message`
+pub fn assert_usage(args: &[&str], message: &str) {
+    let output = pmg(args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "{:?} was not refused: {}", args, stderr);
+    assert!(output.stdout.is_empty(), "{:?} printed {}", args, String::from_utf8_lossy(&output.stdout));
+    assert!(stderr.contains(message), "{:?} printed {}", args, stderr);
+}
@@ -0,0 +1,80 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Checks the records printed by -F/--format.  Scripts depend on these fields, so the tests
+//pin the exact output of schema version 1.
+
+mod common;
+
+use common::{pmg, stdout};
+
+//The heading row of an address or prefix record in schema version 1
+const HEADING: &str = "schema_version,kind,address,prefix,prefix_length,first,last,count,ul_bit,ig_bit";
+
+#[test]
+fn csv_has_every_field_in_order() {
+    let output = stdout(&pmg(&["range", "02:5a:10:00:00:00/40", "-L", "-m", "2", "-F", "csv"]));
+    assert_eq!(output, format!("{}\n\
+        1,address,02:5a:10:00:00:00,02:5a:10:00:00:00/40,40,02:5a:10:00:00:00,02:5a:10:00:00:ff,256,1,0\n\
+        1,address,02:5a:10:00:00:01,02:5a:10:00:00:00/40,40,02:5a:10:00:00:00,02:5a:10:00:00:ff,256,1,0\n", HEADING));
+
+    //A prefix has no address, and an address drawn from no prefix has no prefix fields
+    let output = stdout(&pmg(&["prefix", "split", "02:5a:10:00:00:00/40", "-F", "csv"]));
+    assert_eq!(output.lines().nth(1).unwrap(), "1,prefix,,02:5a:10:00:00:00/41,41,02:5a:10:00:00:00,02:5a:10:00:00:7f,128,1,0");
+    let output = stdout(&pmg(&["-S", "7", "-u", "-F", "csv"]));
+    assert!(output.lines().nth(1).unwrap().ends_with(",,,,,,1,0"), "unexpected output: {}", output);
+}
+
+#[test]
+fn every_format_prints_the_same_record() {
+    let args = ["range", "02:5a:10:00:00:00/40", "-F"];
+
+    let json = stdout(&pmg(&[&args[..], &["json"]].concat()));
+    assert_eq!(json, "[{\"schema_version\":1,\"kind\":\"prefix\",\"address\":null,\"prefix\":\"02:5a:10:00:00:00/40\",\
+        \"prefix_length\":40,\"first\":\"02:5a:10:00:00:00\",\"last\":\"02:5a:10:00:00:ff\",\"count\":256,\"ul_bit\":1,\"ig_bit\":0}]\n");
+
+    let yaml = stdout(&pmg(&[&args[..], &["yaml"]].concat()));
+    assert_eq!(yaml, "- schema_version: 1\n  kind: \"prefix\"\n  address: null\n  prefix: \"02:5a:10:00:00:00/40\"\n  \
+        prefix_length: 40\n  first: \"02:5a:10:00:00:00\"\n  last: \"02:5a:10:00:00:ff\"\n  count: 256\n  ul_bit: 1\n  ig_bit: 0\n");
+
+    let tsv = stdout(&pmg(&[&args[..], &["tsv"]].concat()));
+    assert_eq!(tsv, format!("{}\n1\tprefix\t\t02:5a:10:00:00:00/40\t40\t02:5a:10:00:00:00\t02:5a:10:00:00:ff\t256\t1\t0\n",
+        HEADING.replace(',', "\t")));
+}
+
+#[test]
+fn generator_records_follow_the_notation() {
+    let output = stdout(&pmg(&["-S", "7", "-u", "-N", "3", "-P", "02:5a:10", "-c", "u", "-s", "-", "-F", "tsv"]));
+    let rows: Vec<Vec<&str>> = output.lines().skip(1).map(|line| line.split('\t').collect()).collect();
+    assert_eq!(rows.len(), 3);
+    for row in rows {
+        assert!(row[2].starts_with("02-5A-10-"), "unexpected row: {:?}", row);
+        assert_eq!(&row[3..], ["02-5A-10-00-00-00/24", "24", "02-5A-10-00-00-00", "02-5A-10-FF-FF-FF", "16777216", "1", "0"]);
+    }
+
+    //An empty listing is still valid JSON and YAML
+    let output = pmg(&["prefix", "overlap", "02:5a:10/24", "02:5b:10/24", "-F", "json"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "[]\n");
+}
+
+#[test]
+fn unknown_format_is_an_error() {
+    for args in [&["-u", "-F", "xml"][..], &["range", "02:5a:10/24", "-F", "xml"][..], &["prefix", "split", "02:5a:10/24", "-F", "xml"][..]] {
+        let output = pmg(args);
+        assert_eq!(output.status.code(), Some(2));
+        assert!(output.stdout.is_empty());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("expected text, json, csv, yaml or tsv"));
+    }
+}
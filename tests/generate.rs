@@ -0,0 +1,96 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Checks that a batch holds exactly the number of values asked for, none of them twice, both when
+//values are drawn at random and when a batch covers most of its space.
+
+extern crate pmg;
+
+mod common;
+
+use common::pmg;
+use pmg::{GenerateError, Generator, MacPrefix, SeededRng};
+use std::collections::HashSet;
+use std::hash::Hash;
+
+fn generator(seed: u64) -> Generator<SeededRng> {
+    Generator::new(SeededRng::new(seed))
+}
+
+//Checks the batch is `count` values long with no value in it twice
+fn assert_distinct<T: Eq + Hash>(batch: &[T], count: usize) {
+    assert_eq!(batch.len(), count);
+    assert_eq!(batch.iter().collect::<HashSet<_>>().len(), count, "a value was repeated");
+}
+
+#[test]
+fn address_batches_are_distinct_and_exact() {
+    assert_distinct(&generator(1).addresses(5000).unwrap(), 5000);
+
+    let prefix: MacPrefix = "02:5a:10:00:00:00/40".parse().unwrap();
+    for &count in &[1, 100, 128, 129, 255, 256] {
+        let batch = generator(2).addresses_within(&prefix, count).unwrap();
+        assert_distinct(&batch, count);
+        assert!(batch.iter().all(|mac| prefix.contains(mac)));
+    }
+}
+
+#[test]
+fn prefix_batches_are_distinct_and_exact() {
+    //There are only 64 locally administered unicast /8 prefixes
+    for &count in &[1, 32, 33, 64] {
+        let batch = generator(3).prefixes_of_len(8, count).unwrap();
+        assert_distinct(&batch, count);
+        assert!(batch.iter().all(|prefix| prefix.is_local_unicast() && prefix.prefix_len() == 8));
+    }
+    assert_distinct(&generator(4).prefixes_of_len(28, 1000).unwrap(), 1000);
+
+    let prefix: MacPrefix = "02:5a:10:00:00:00/24".parse().unwrap();
+    for &count in &[1, 32, 33, 64] {
+        let batch = generator(5).prefixes_within(&prefix, 30, count).unwrap();
+        assert_distinct(&batch, count);
+        assert!(batch.iter().all(|within| prefix.contains(&within.first()) && within.prefix_len() == 30));
+    }
+}
+
+#[test]
+fn a_count_larger_than_the_space_is_refused() {
+    let prefix: MacPrefix = "02:5a:10:00:00:00/44".parse().unwrap();
+    match generator(6).addresses_within(&prefix, 17) {
+        Err(GenerateError::Exhausted { requested: 17, available: 16 }) => {}
+        other => panic!("unexpected result {:?}", other.map(|batch| batch.len())),
+    }
+
+    //On the command line it is a usage error, refused before anything is drawn
+    let output = pmg(&["-P", "02:5a:10:00:00:00/44", "-u", "-N", "17"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("only 16 distinct values"));
+    assert_eq!(pmg(&["-l", "8", "-N", "65"]).status.code(), Some(2));
+
+    //A count the space holds but memory cannot is an error rather than an abort
+    let output = pmg(&["-u", "-N", "70000000000000"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not enough memory"));
+}
+
+#[test]
+fn the_command_line_prints_every_value_once() {
+    for args in [&["-u", "-N", "2000", "-p"][..], &["-P", "02:5a:10:00:00:00/40", "-u", "-N", "256", "-p"][..], &["-l", "8", "-N", "64", "-p"][..]] {
+        let output = pmg(args);
+        assert!(output.status.success());
+        let lines: Vec<String> = String::from_utf8(output.stdout).unwrap().lines().map(String::from).collect();
+        let count: usize = args[args.len() - 2].parse().unwrap();
+        assert_distinct(&lines, count);
+    }
+}
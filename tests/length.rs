@@ -0,0 +1,84 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Checks prefixes that do not end on an octet boundary: their address counts and ends, and the
+//-l/--length values gen refuses.
+
+extern crate pmg;
+
+mod common;
+
+use common::{assert_usage, pmg, stdout};
+use pmg::{Generator, MacAddress, MacPrefix, SeededRng};
+
+fn mac(value: &str) -> MacAddress {
+    value.parse().unwrap()
+}
+
+#[test]
+fn counts_are_exact_for_any_length() {
+    let prefix: MacPrefix = "02:5a:10:40:00:00/28".parse().unwrap();
+    assert_eq!(prefix.count(), 1_048_576);
+    assert_eq!(prefix.first(), mac("02:5a:10:40:00:00"));
+    assert_eq!(prefix.last(), mac("02:5a:10:4f:ff:ff"));
+
+    let prefix: MacPrefix = "02:5a:10:40:00:00/36".parse().unwrap();
+    assert_eq!(prefix.count(), 4096);
+    assert_eq!(prefix.first(), mac("02:5a:10:40:00:00"));
+    assert_eq!(prefix.last(), mac("02:5a:10:40:0f:ff"));
+
+    for len in 8..48u8 {
+        let prefix = MacPrefix::new(mac("02:00:00:00:00:00"), len).unwrap();
+        assert_eq!(prefix.count(), 1 << (48 - len));
+        assert_eq!(u64::from(prefix.last()) - u64::from(prefix.first()) + 1, prefix.count());
+    }
+}
+
+#[test]
+fn generated_prefixes_have_the_length_asked_for() {
+    let mut generator = Generator::new(SeededRng::new(7));
+    let within: MacPrefix = "02:5a:10:40:00:00/28".parse().unwrap();
+    for prefix in generator.prefixes_within(&within, 36, 100).unwrap() {
+        assert_eq!(prefix.prefix_len(), 36);
+        assert_eq!(prefix.count(), 4096);
+        assert!(within.contains_prefix(&prefix), "{} is not inside {}", prefix, within);
+    }
+}
+
+#[test]
+fn labelled_lines_print_the_count() {
+    assert_eq!(stdout(&pmg(&["-S", "1", "-P", "02:5a:10:40:00:00/28", "-l", "36"])),
+        "Private MAC Prefix:    02:5a:10:4c:b0:00/36\n\
+         Assignable Addresses:  4096\n\
+         Assigned Addresses:    02:5a:10:4c:b0:00 - 02:5a:10:4c:bf:ff\n\n");
+
+    let output = stdout(&pmg(&["-S", "1", "-l", "28"]));
+    assert!(output.contains("Assignable Addresses:  1048576\n"), "unexpected output: {}", output);
+
+    assert_eq!(stdout(&pmg(&["range", "02:5a:10:40:00:00/28"])),
+        "Private MAC Prefix:    02:5a:10:40:00:00/28\n\
+         Assignable Addresses:  1048576\n\
+         Assigned Addresses:    02:5a:10:40:00:00 - 02:5a:10:4f:ff:ff\n");
+}
+
+#[test]
+fn lengths_that_cannot_be_used_are_refused() {
+    assert_usage(&["-P", "02:5a:10:40:00:00/28", "-l", "28"], "a /28 prefix leaves no room to generate a /28 prefix inside it");
+    assert_usage(&["-P", "02:5a:10:40:00:00/28", "-l", "20"], "a /28 prefix leaves no room to generate a /20 prefix inside it");
+    assert_usage(&["-P", "02:5a:10:40:00:00/44"], "a /44 prefix leaves no room to generate a /40 prefix inside it");
+    assert_usage(&["-u", "-l", "28"], "-l/--length cannot be used with -u/--unique");
+    assert_usage(&["-P", "02:5a:10", "-u", "-l", "36"], "-l/--length cannot be used with -u/--unique");
+    assert_usage(&["-l", "48"], "invalid -l/--length '48'");
+    assert_usage(&["-l", "7"], "invalid -l/--length '7'");
+}
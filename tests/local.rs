@@ -0,0 +1,67 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Checks that -P/--prefix refuses a prefix that is not locally administered unicast unless
+//-f/--force is given, and that the library keeps to locally administered unicast by itself.
+
+extern crate pmg;
+
+mod common;
+
+use common::pmg;
+use pmg::{Generator, MacPrefix, SeededRng};
+
+#[test]
+fn prefixes_that_are_not_local_unicast_are_refused() {
+    for (prefix, problem) in [("00:1b:21", "universally administered"), ("03:00:00", "multicast"),
+        ("01:00:5e", "universally administered and multicast")] {
+        for args in [&["-P", prefix, "-u"][..], &["-P", prefix, "-l", "36"][..]] {
+            let output = pmg(args);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert_eq!(output.status.code(), Some(2), "{:?} was not refused", args);
+            assert!(output.stdout.is_empty());
+            assert!(stderr.contains(&format!("is {}, not locally administered unicast", problem)), "{:?} printed {}", args, stderr);
+            assert!(stderr.contains("-f/--force"));
+        }
+    }
+}
+
+#[test]
+fn force_uses_the_prefix_with_a_warning() {
+    let output = pmg(&["-S", "1", "-P", "00:1b:21", "-u", "-p", "-f"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "00:1b:21:ec:be:eb\n");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "pmg: warning: prefix 00:1b:21:00:00:00/24 is universally administered\n");
+
+    //A local unicast prefix never warns
+    let output = pmg(&["-S", "1", "-P", "02:5a:10", "-u", "-p", "-f"]);
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn random_values_are_local_unicast() {
+    let mut generator = Generator::new(SeededRng::new(7));
+    for mac in generator.addresses(1000).unwrap() {
+        assert!(mac.is_local() && !mac.is_multicast(), "{} is not local unicast", mac);
+    }
+    for prefix in generator.prefixes_of_len(24, 1000).unwrap() {
+        assert!(prefix.is_local_unicast(), "{} is not local unicast", prefix);
+    }
+
+    let within: MacPrefix = "02:5a:10".parse().unwrap();
+    for mac in generator.addresses_within(&within, 1000).unwrap() {
+        assert!(within.first() <= mac && mac <= within.last(), "{} is not inside {}", mac, within);
+    }
+}
@@ -0,0 +1,135 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Checks the MacAddress and MacPrefix values: parsing, printing and the conversions to and from
+//integers and octets.
+
+extern crate pmg;
+
+use pmg::{MacAddress, MacPrefix, ParseMacError};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+fn mac(value: &str) -> MacAddress {
+    value.parse().unwrap()
+}
+
+fn prefix(value: &str) -> MacPrefix {
+    value.parse().unwrap()
+}
+
+#[test]
+fn addresses_print_as_they_parse() {
+    for value in ["02:5a:10:00:00:01", "00:00:00:00:00:00", "ff:ff:ff:ff:ff:ff", "fe:dc:ba:98:76:54"] {
+        assert_eq!(mac(value).to_string(), value);
+        assert_eq!(mac(&mac(value).to_string()), mac(value));
+    }
+
+    //Every notation prints back as lower case colons
+    assert_eq!(mac(" 02-5A-10-00-00-01\n").to_string(), "02:5a:10:00:00:01");
+    assert_eq!(mac("025A.10ff.0001").octets(), [0x02, 0x5a, 0x10, 0xff, 0x00, 0x01]);
+}
+
+#[test]
+fn addresses_that_cannot_be_parsed_say_why() {
+    assert_eq!("02:5a:10:00:00".parse::<MacAddress>(), Err(ParseMacError::InvalidFormat));
+    assert_eq!("02:5a:10:00:00:01:02".parse::<MacAddress>(), Err(ParseMacError::InvalidFormat));
+    assert_eq!("02:5a-10:00:00:01".parse::<MacAddress>(), Err(ParseMacError::InvalidFormat));
+    assert_eq!("02:5a:10:00:00:0g".parse::<MacAddress>(), Err(ParseMacError::InvalidDigit));
+    assert_eq!("025a1000000".parse::<MacAddress>(), Err(ParseMacError::InvalidLength));
+    assert_eq!("025a100000011".parse::<MacAddress>(), Err(ParseMacError::InvalidLength));
+    assert_eq!("02_5a_10_00_00_01".parse::<MacAddress>(), Err(ParseMacError::InvalidDigit));
+    assert_eq!("".parse::<MacAddress>(), Err(ParseMacError::InvalidLength));
+}
+
+#[test]
+fn addresses_convert_to_and_from_integers() {
+    assert_eq!(u64::from(mac("02:5a:10:00:00:01")), 0x025a_1000_0001);
+    assert_eq!(MacAddress::try_from(0x025a_1000_0001u64), Ok(mac("02:5a:10:00:00:01")));
+    assert_eq!(MacAddress::try_from(0xffff_ffff_ffffu64), Ok(mac("ff:ff:ff:ff:ff:ff")));
+    assert_eq!(MacAddress::try_from(1u64 << 48), Err(ParseMacError::OutOfRange));
+
+    for value in [0u64, 1, 0xff, 0x0100, 0x0123_4567_89ab, 0xffff_ffff_ffff] {
+        assert_eq!(u64::from(MacAddress::try_from(value).unwrap()), value);
+    }
+
+    let octets = [0x02, 0x5a, 0x10, 0x00, 0x00, 0x01];
+    assert_eq!(MacAddress::from(octets), MacAddress::new(octets));
+    assert_eq!(<[u8; 6]>::from(MacAddress::from(octets)), octets);
+}
+
+#[test]
+fn addresses_order_and_hash_by_value() {
+    let mut macs = vec![mac("02:5a:10:00:01:00"), mac("02:5a:10:00:00:ff"), mac("00:00:00:00:00:01")];
+    macs.sort();
+    assert_eq!(macs, [mac("00:00:00:00:00:01"), mac("02:5a:10:00:00:ff"), mac("02:5a:10:00:01:00")]);
+
+    let set: HashSet<MacAddress> = ["02:5a:10:00:00:01", "02-5A-10-00-00-01", "025a.1000.0001"].iter().map(|value| mac(value)).collect();
+    assert_eq!(set.len(), 1);
+}
+
+#[test]
+fn prefixes_print_their_octets() {
+    let prefix = MacPrefix::from_octets(&[0x02, 0x5a, 0x10]).unwrap();
+    assert_eq!(prefix.to_string(), "02:5a:10:00:00:00/24");
+    assert_eq!(prefix.octets(), [0x02, 0x5a, 0x10]);
+    assert_eq!(prefix.prefix_len(), 24);
+    assert_eq!(prefix.count(), 16_777_216);
+    assert_eq!(prefix.first(), mac("02:5a:10:00:00:00"));
+    assert_eq!(prefix.last(), mac("02:5a:10:ff:ff:ff"));
+
+    let prefix = MacPrefix::from_octets(&[0x02, 0x5a, 0x10, 0x40, 0x00]).unwrap();
+    assert_eq!(prefix.to_string(), "02:5a:10:40:00:00/40");
+    assert_eq!(prefix.count(), 256);
+    assert_eq!(prefix.last(), mac("02:5a:10:40:00:ff"));
+
+    //A prefix must leave room for at least one assignable octet
+    assert_eq!(MacPrefix::from_octets(&[]), None);
+    assert_eq!(MacPrefix::from_octets(&[0x02; 6]), None);
+}
+
+#[test]
+fn prefixes_print_as_they_parse() {
+    for value in ["02:5a:10:00:00:00/24", "02:5a:10:40:00:00/28", "02:00:00:00:00:00/8", "02:5a:10:00:00:00/47"] {
+        assert_eq!(prefix(value).to_string(), value);
+    }
+
+    //Octets without a length are as long as the octets, and may be given a longer length
+    assert_eq!(prefix("02:5a:10").to_string(), "02:5a:10:00:00:00/24");
+    assert_eq!(prefix("02-5A-10-40-00").to_string(), "02:5a:10:40:00:00/40");
+    assert_eq!(prefix("025a10").prefix_len(), 24);
+    assert_eq!(prefix("02:5a:10:40/28").to_string(), "02:5a:10:40:00:00/28");
+}
+
+#[test]
+fn prefixes_that_cannot_be_parsed_say_why() {
+    assert_eq!("02:5a:10:40:00:00/24".parse::<MacPrefix>(), Err(ParseMacError::HostBitsSet));
+    assert_eq!("02:5a:10:00:00:00/48".parse::<MacPrefix>(), Err(ParseMacError::InvalidPrefixLength));
+    assert_eq!("02:5a:10:00:00:00/7".parse::<MacPrefix>(), Err(ParseMacError::InvalidPrefixLength));
+    assert_eq!("02:5a:10:00:00:00/".parse::<MacPrefix>(), Err(ParseMacError::InvalidPrefixLength));
+    assert_eq!("02:5a:10:00:00:01".parse::<MacPrefix>(), Err(ParseMacError::InvalidPrefixLength));
+    assert!("02:5a:1".parse::<MacPrefix>().is_err());
+    assert!("02:5a:10:00:00:00:00".parse::<MacPrefix>().is_err());
+}
+
+#[test]
+fn prefixes_clear_their_host_bits() {
+    let within = MacPrefix::new(mac("02:5a:10:4f:ff:ff"), 28).unwrap();
+    assert_eq!(within, prefix("02:5a:10:40:00:00/28"));
+    assert_eq!(within.first(), mac("02:5a:10:40:00:00"));
+    assert_eq!(within.last(), mac("02:5a:10:4f:ff:ff"));
+
+    assert_eq!(MacPrefix::from_octets(&[0x02, 0x5a, 0x10]), Some(prefix("02:5a:10/24")));
+    assert_eq!(MacPrefix::new(mac("02:00:00:00:00:00"), 48), None);
+}
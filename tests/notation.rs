@@ -0,0 +1,88 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Checks how addresses are written out: the separator, the case, the group size and Cisco's
+//dotted notation.
+
+extern crate pmg;
+
+mod common;
+
+use common::{pmg, stdout};
+use pmg::{Case, MacAddress, MacPrefix, Notation};
+
+fn mac(value: &str) -> MacAddress {
+    value.parse().unwrap()
+}
+
+#[test]
+fn cisco_notation_groups_words() {
+    let address = mac("02:5a:10:00:00:01");
+    assert_eq!(Notation::cisco(Case::Lower).address(&address), "025a.1000.0001");
+    assert_eq!(Notation::cisco(Case::Upper).address(&mac("fe:dc:ba:98:76:54")), "FEDC.BA98.7654");
+
+    //The dotted form parses back to the same address
+    assert_eq!(mac(&Notation::cisco(Case::Lower).address(&address)), address);
+}
+
+#[test]
+fn group_sizes_split_the_octets() {
+    let address = mac("02:5a:10:00:00:01");
+    let notation = |separator, group| Notation { separator, case: Case::Lower, group };
+
+    assert_eq!(notation(Some(':'), 1).address(&address), "02:5a:10:00:00:01");
+    assert_eq!(notation(Some('-'), 2).address(&address), "025a-1000-0001");
+    assert_eq!(notation(Some('-'), 3).address(&address), "025a10-000001");
+    assert_eq!(notation(None, 2).address(&address), "025a10000001");
+
+    //A prefix prints only its octets, and a group running past the last octet is written short
+    let prefix = MacPrefix::from_octets(&[0x02, 0x5a, 0x10]).unwrap();
+    assert_eq!(notation(Some('.'), 2).prefix(&prefix), "025a.10");
+    assert_eq!(Notation::default().prefix(&prefix), "02:5a:10");
+}
+
+#[test]
+fn dotted_separator_groups_words_on_the_command_line() {
+    assert_eq!(stdout(&pmg(&["-S", "1", "-u", "-p", "-s", "."])), "920a.2dec.beeb\n");
+    assert_eq!(stdout(&pmg(&["-S", "1", "-u", "-p", "-s", ".", "-c", "u"])), "920A.2DEC.BEEB\n");
+    assert_eq!(stdout(&pmg(&["-S", "1", "-u", "-p", "-s", ".", "-g", "1"])), "92.0a.2d.ec.be.eb\n");
+    assert_eq!(stdout(&pmg(&["-S", "1", "-u", "-p", "-s", "-", "-g", "3"])), "920a2d-ecbeeb\n");
+    assert_eq!(stdout(&pmg(&["-S", "1", "-u", "-p", "-g", "2"])), "920a:2dec:beeb\n");
+    assert_eq!(stdout(&pmg(&["-S", "1", "-u", "-p", "-s", ".", "-n"])), "920a2decbeeb\n");
+
+    let output = pmg(&["-S", "1", "-u", "-g", "4"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("expected 1, 2 or 3"));
+}
+
+#[test]
+fn labelled_lines_follow_the_notation() {
+    assert_eq!(stdout(&pmg(&["range", "02:5a:10:40:00:00/28", "-s", "."])),
+        "Private MAC Prefix:    025a.1040.0000/28\n\
+         Assignable Addresses:  1048576\n\
+         Assigned Addresses:    025a.1040.0000 - 025a.104f.ffff\n");
+
+    assert_eq!(stdout(&pmg(&["-S", "1", "-r", "3", "-s", "."])),
+        "Private MAC Prefix:    920a.2d\n\
+         Assignable Addresses:  16777216\n\
+         Assigned Addresses:    920a.2d00.0000 - 920a.2dff.ffff\n\n");
+
+    assert_eq!(stdout(&pmg(&["-S", "1", "-l", "28", "-s", "."])),
+        "Private MAC Prefix:    920a.2de0.0000/28\n\
+         Assignable Addresses:  1048576\n\
+         Assigned Addresses:    920a.2de0.0000 - 920a.2def.ffff\n\n");
+
+    let listed = stdout(&pmg(&["range", "02:5a:10:40:00:00/28", "-L", "-m", "2", "-s", ".", "-c", "u"]));
+    assert_eq!(listed, "025A.1040.0000\n025A.1040.0001\n");
+}
@@ -0,0 +1,481 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Checks that many pmg processes can share one pool file.  Each test runs the
+//pmg binary in its own scratch directory.
+
+extern crate pmg;
+
+use pmg::{Backend, MacAddress, Metadata, Pool};
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output, Stdio};
+
+//How many processes allocate at the same moment
+const WORKERS: usize = 16;
+
+//How many addresses each process allocates
+const ALLOCATIONS: usize = 8;
+
+//Creates an empty scratch directory for a test
+fn scratch(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("pmg-test-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn pmg(args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_pmg"));
+    command.args(args).stdout(Stdio::piped()).stderr(Stdio::piped());
+    command
+}
+
+fn init(path: &Path, prefix: &str) {
+    let output = pmg(&["pool", "init", path.to_str().unwrap(), "-P", prefix]).output().unwrap();
+    assert!(output.status.success(), "init failed: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+//Starts a process that allocates several addresses one after another
+fn spawn_worker(path: &Path, worker: usize) -> Child {
+    let script = format!("for i in $(seq {}); do \"$0\" pool alloc \"$1\" -o worker-{} || exit 1; done", ALLOCATIONS, worker);
+    Command::new("sh")
+        .args(["-c", &script, env!("CARGO_BIN_EXE_pmg"), path.to_str().unwrap()])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap()
+}
+
+fn lines(output: &Output) -> Vec<String> {
+    String::from_utf8_lossy(&output.stdout).lines().map(|line| line.to_string()).collect()
+}
+
+#[test]
+fn concurrent_allocations_are_distinct() {
+    let dir = scratch("concurrent");
+    let path = dir.join("lab.pool");
+    init(&path, "02:5a:10:00:00:00/32");
+
+    let workers: Vec<Child> = (0..WORKERS).map(|worker| spawn_worker(&path, worker)).collect();
+
+    //Read the pool while it is being changed.  A reader must never see a partly written file.
+    let mut reads = 0;
+    loop {
+        let output = pmg(&["pool", "list", path.to_str().unwrap()]).output().unwrap();
+        assert!(output.status.success(), "list failed: {}", String::from_utf8_lossy(&output.stderr));
+        reads += 1;
+
+        if lines(&output).len() == WORKERS * ALLOCATIONS || reads > 1000 {
+            break;
+        }
+    }
+
+    let mut handed_out = HashSet::new();
+    for worker in workers {
+        let output = worker.wait_with_output().unwrap();
+        assert!(output.status.success(), "alloc failed: {}", String::from_utf8_lossy(&output.stderr));
+
+        for line in lines(&output) {
+            let mac: MacAddress = line.parse().unwrap();
+            assert!(handed_out.insert(mac), "{} was handed out twice", mac);
+        }
+    }
+    assert_eq!(handed_out.len(), WORKERS * ALLOCATIONS);
+
+    //The saved pool agrees with what the processes printed
+    let pool = Pool::open(&path).unwrap();
+    assert_eq!(pool.allocated(), (WORKERS * ALLOCATIONS) as u64);
+    assert!(handed_out.iter().all(|mac| pool.is_allocated(mac)));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn concurrent_allocations_fill_a_small_pool_exactly() {
+    let dir = scratch("full");
+    let path = dir.join("small.pool");
+    //A /41 has 128 addresses, fewer than the workers ask for in total
+    init(&path, "02:5a:10:00:00:00/41");
+
+    let workers: Vec<Child> = (0..WORKERS).map(|worker| spawn_worker(&path, worker)).collect();
+
+    let mut handed_out = HashSet::new();
+    for worker in workers {
+        let output = worker.wait_with_output().unwrap();
+        for line in lines(&output) {
+            let mac: MacAddress = line.parse().unwrap();
+            assert!(handed_out.insert(mac), "{} was handed out twice", mac);
+        }
+    }
+
+    assert_eq!(handed_out.len(), 128);
+    assert_eq!(Pool::open(&path).unwrap().allocated(), 128);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn stale_temporary_file_is_ignored() {
+    let dir = scratch("stale");
+    let path = dir.join("lab.pool");
+    init(&path, "02:5a:10:00:00:00/40");
+
+    //A crash part way through a save leaves a partly written temporary file behind
+    fs::write(dir.join("lab.pool.tmp"), b"PMGPOOL\n\x00").unwrap();
+
+    let output = pmg(&["pool", "alloc", path.to_str().unwrap()]).output().unwrap();
+    assert!(output.status.success(), "alloc failed: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(lines(&output), vec!["02:5a:10:00:00:00".to_string()]);
+    assert_eq!(Pool::open(&path).unwrap().allocated(), 1);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn init_never_replaces_a_pool() {
+    let dir = scratch("init");
+    let path = dir.join("lab.pool");
+    init(&path, "02:5a:10:00:00:00/40");
+
+    let output = pmg(&["pool", "alloc", path.to_str().unwrap()]).output().unwrap();
+    assert!(output.status.success());
+
+    let output = pmg(&["pool", "init", path.to_str().unwrap(), "-P", "02:5a:10:00:00:00/40"]).output().unwrap();
+    assert!(!output.status.success());
+    assert_eq!(Pool::open(&path).unwrap().allocated(), 1);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn expired_leases_are_quarantined_before_reuse() {
+    let prefix = "02:5a:10:00:00:00/46".parse().unwrap();
+    let mut pool = Pool::new(prefix).unwrap();
+    pool.set_quarantine(100);
+
+    let leased = pool.allocate(Metadata { owner: Some("ci".to_string()), ..Metadata::default() }, Some(60), 1000).unwrap().mac;
+    let held = pool.allocate(Metadata::default(), None, 1000).unwrap().mac;
+
+    //Nothing has run out yet
+    assert!(pool.reap(1059).is_empty());
+
+    let reaped = pool.reap(1060);
+    assert_eq!(reaped.len(), 1);
+    assert_eq!(reaped[0].mac, leased);
+    assert!(!pool.is_allocated(&leased));
+    assert!(pool.is_allocated(&held));
+    //The quarantine runs from when the lease ran out, not from when it was reaped
+    assert_eq!(pool.quarantined_until(&leased), Some(1160));
+
+    //The two free addresses are handed out before the quarantined one
+    let third = pool.allocate(Metadata::default(), None, 1100).unwrap().mac;
+    let fourth = pool.allocate(Metadata::default(), None, 1100).unwrap().mac;
+    assert!(third != leased && fourth != leased);
+    assert!(pool.allocate(Metadata::default(), None, 1159).is_err());
+    assert_eq!(pool.allocate(Metadata::default(), None, 1160).unwrap().mac, leased);
+}
+
+#[test]
+fn renewal_extends_by_the_current_lease() {
+    let prefix = "02:5a:10:00:00:00/46".parse().unwrap();
+    let mut pool = Pool::new(prefix).unwrap();
+
+    let mac = pool.allocate(Metadata::default(), Some(60), 1000).unwrap().mac;
+    assert_eq!(pool.renew(&mac, None, 1050).unwrap().lease.unwrap().expires, 1110);
+    assert_eq!(pool.renew(&mac, Some(10), 1100).unwrap().lease.unwrap().expires, 1110);
+
+    //A renewed lease survives a reap that would have caught the first lease
+    assert!(pool.reap(1100).is_empty());
+
+    //The lease survives a round trip through the file layout
+    let mut file = Vec::new();
+    pool.write_to(&mut file).unwrap();
+    let read = Pool::read_from(&file[..]).unwrap();
+    assert_eq!(read.get(&mac), pool.get(&mac));
+}
+
+#[test]
+fn metadata_survives_a_save() {
+    let dir = scratch("metadata");
+    let path = dir.join("lab.pool");
+    init(&path, "02:5a:10:00:00:00/40");
+
+    let output = pmg(&["pool", "alloc", path.to_str().unwrap(), "-o", "vm-42", "-T", "env=staging", "--tag", "team=net", "-C", "build farm"])
+        .output().unwrap();
+    assert!(output.status.success(), "alloc failed: {}", String::from_utf8_lossy(&output.stderr));
+    let mac: MacAddress = lines(&output)[0].parse().unwrap();
+
+    let pool = Pool::open(&path).unwrap();
+    let metadata = &pool.get(&mac).unwrap().metadata;
+    assert_eq!(metadata.owner.as_deref(), Some("vm-42"));
+    assert_eq!(metadata.comment.as_deref(), Some("build farm"));
+    assert!(metadata.has_tag("env", Some("staging")));
+    assert!(metadata.has_tag("team", None));
+    assert!(!metadata.has_tag("env", Some("prod")));
+
+    let output = pmg(&["pool", "find", path.to_str().unwrap(), "-T", "env=staging", "-F", "json"]).output().unwrap();
+    let json = String::from_utf8_lossy(&output.stdout);
+    assert!(json.starts_with("[{\"schema_version\":1,\"mac\":\"02:5a:10:00:00:00\""), "unexpected output: {}", json);
+    assert!(json.contains("\"tags\":{\"env\":\"staging\",\"team\":\"net\"}"), "unexpected output: {}", json);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn usage_counts_each_state() {
+    let prefix = "02:5a:10:00:00:00/44".parse().unwrap();
+    let mut pool = Pool::new(prefix).unwrap();
+    pool.set_quarantine(100);
+
+    pool.allocate(Metadata::default(), None, 1000).unwrap();
+    pool.allocate(Metadata::default(), Some(10), 1000).unwrap();
+    pool.allocate(Metadata::default(), Some(20), 1000).unwrap();
+    pool.allocate(Metadata::default(), Some(60), 1000).unwrap();
+    pool.reap(1015);
+
+    let usage = pool.usage(1030);
+    assert_eq!((usage.capacity, usage.allocated, usage.expired, usage.quarantined, usage.free), (16, 3, 1, 1, 12));
+    assert_eq!(usage.used_percent(), 25.0);
+
+    //A quarantine that is over counts as free before anything frees it
+    assert_eq!(pool.usage(1110).quarantined, 0);
+    assert_eq!(pool.usage(1110).free, 13);
+}
+
+#[test]
+fn stats_textfile_is_replaced_whole() {
+    let dir = scratch("stats");
+    let path = dir.join("lab.pool");
+    init(&path, "02:5a:10:00:00:00/40");
+    pmg(&["pool", "alloc", path.to_str().unwrap()]).output().unwrap();
+
+    let output = pmg(&["pool", "stats", path.to_str().unwrap(), "-F", "json"]).output().unwrap();
+    assert_eq!(lines(&output), ["{\"schema_version\":1,\"prefix\":\"02:5a:10:00:00:00/40\",\"total\":256,\"allocated\":1,\"expired\":0,\"quarantined\":0,\"free\":255,\"percent_used\":0.39}"]);
+
+    for _ in 0..2 {
+        let output = pmg(&["pool", "stats", path.to_str().unwrap(), "--textfile-dir", dir.to_str().unwrap()]).output().unwrap();
+        assert!(output.status.success(), "stats failed: {}", String::from_utf8_lossy(&output.stderr));
+        assert!(output.stdout.is_empty());
+    }
+
+    //Only the pool, its lock, its log and the metrics are left, with no temporary file
+    let mut names: Vec<String> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned()).collect();
+    names.sort();
+    assert_eq!(names, ["lab.pool", "lab.pool.lock", "lab.pool.log", "pmg_pool_lab.prom"]);
+
+    let metrics = fs::read_to_string(dir.join("pmg_pool_lab.prom")).unwrap();
+    assert!(metrics.contains("\npmg_pool_free{pool=\""), "unexpected metrics: {}", metrics);
+    assert!(metrics.contains("prefix=\"02:5a:10:00:00:00/40\"} 255\n"), "unexpected metrics: {}", metrics);
+
+    let output = pmg(&["pool", "stats", path.to_str().unwrap(), "-F", "xml"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn audit_log_replays_to_the_pool() {
+    let dir = scratch("audit");
+    let path = dir.join("lab.pool");
+    let file = path.to_str().unwrap();
+    init(&path, "02:5a:10:00:00:00/40");
+
+    let first = lines(&pmg(&["pool", "alloc", file, "-o", "vm-1", "-T", "env=ci", "-t", "1h"]).output().unwrap())[0].clone();
+    let second = lines(&pmg(&["pool", "alloc", file, "-o", "vm-2"]).output().unwrap())[0].clone();
+    pmg(&["pool", "renew", file, &first, "-t", "2h"]).output().unwrap();
+    pmg(&["pool", "release", file, &first]).output().unwrap();
+
+    //Every change is one line, with the address it changed
+    let log = fs::read_to_string(dir.join("lab.pool.log")).unwrap();
+    let ops: Vec<&str> = log.lines().map(|line| line.split("\"op\":\"").nth(1).unwrap().split('"').next().unwrap()).collect();
+    assert_eq!(ops, ["init", "alloc", "alloc", "renew", "release"]);
+
+    let output = pmg(&["pool", "history", file, &first, "-F", "json"]).output().unwrap();
+    let history = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(history.matches("\"op\":").count(), 3, "unexpected history: {}", history);
+    assert!(history.starts_with("[{\"schema_version\":1,\"time\":"), "unexpected history: {}", history);
+    assert!(history.contains("\"owner\":\"vm-1\",\"tags\":{\"env\":\"ci\"}"), "unexpected history: {}", history);
+    assert!(!history.contains(&second), "unexpected history: {}", history);
+
+    let output = pmg(&["pool", "verify", file]).output().unwrap();
+    assert!(output.status.success(), "verify failed: {}", String::from_utf8_lossy(&output.stdout));
+
+    //A change that bypasses the log is caught
+    Pool::update(&path, |pool| pool.release(&second.parse().unwrap())).unwrap();
+    let output = pmg(&["pool", "verify", file]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(lines(&output), [format!("{} is allocated in the log but not in the pool", second)]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn text_pool_keeps_everything_a_binary_pool_does() {
+    let prefix = "02:5a:10:00:00:00/44".parse().unwrap();
+    let mut pool = Pool::new(prefix).unwrap();
+    pool.set_backend(Backend::Text);
+    pool.set_quarantine(100);
+
+    let mut metadata = Metadata {
+        owner: Some("vm 42=%".to_string()),
+        comment: Some("# not a comment\nsecond line".to_string()),
+        ..Metadata::default()
+    };
+    metadata.tags.insert("env".to_string(), "ci".to_string());
+    let kept = pool.allocate(metadata, Some(60), 1000).unwrap().mac;
+    let reaped = pool.allocate(Metadata::default(), Some(10), 1000).unwrap().mac;
+    pool.reap(1020);
+
+    let mut file = Vec::new();
+    pool.write_to(&mut file).unwrap();
+    let text = String::from_utf8(file.clone()).unwrap();
+    assert!(text.starts_with("pmg-pool text 1\n"), "unexpected file: {}", text);
+    assert!(text.contains(&format!("\n{} quarantined until=1110\n", reaped)), "unexpected file: {}", text);
+
+    let read = Pool::read_from(&file[..]).unwrap();
+    assert_eq!(read.backend(), Backend::Text);
+    assert_eq!(read.quarantine(), 100);
+    assert_eq!(read.get(&kept), pool.get(&kept));
+    assert_eq!(read.quarantined_until(&reaped), Some(1110));
+
+    //Writing what was read gives back the same bytes
+    let mut again = Vec::new();
+    read.write_to(&mut again).unwrap();
+    assert_eq!(again, file);
+}
+
+#[test]
+fn text_pools_merge_line_by_line() {
+    let dir = scratch("merge");
+    let path = dir.join("lab.pool");
+    let file = path.to_str().unwrap();
+    let output = pmg(&["pool", "init", file, "-P", "02:5a:10:00:00:00/40", "-b", "text"]).output().unwrap();
+    assert!(output.status.success(), "init failed: {}", String::from_utf8_lossy(&output.stderr));
+    pmg(&["pool", "alloc", file, "-o", "base"]).output().unwrap();
+    let base = fs::read_to_string(&path).unwrap();
+
+    //Two branches each allocate from the same base
+    let branch = |owner: &str| {
+        fs::write(&path, &base).unwrap();
+        pmg(&["pool", "alloc", file, "-o", owner]).output().unwrap();
+        fs::read_to_string(&path).unwrap()
+    };
+    let ours = branch("ours");
+    let theirs = branch("theirs");
+
+    //Git's union driver keeps the lines of both sides
+    let mut merged: Vec<&str> = ours.lines().chain(theirs.lines()).collect();
+    merged.dedup();
+    let mut seen = HashSet::new();
+    merged.retain(|line| seen.insert(*line));
+    fs::write(&path, merged.join("\n") + "\n").unwrap();
+
+    let output = pmg(&["pool", "fsck", file]).output().unwrap();
+    assert!(output.status.success(), "fsck failed: {}", String::from_utf8_lossy(&output.stdout));
+    assert_eq!(Pool::open(&path).unwrap().allocated(), 3);
+
+    //A merge that gives one address to both sides is refused until it is fixed by hand
+    let line = merged.iter().find(|line| line.contains("owner=ours")).unwrap().to_string();
+    let duplicate = line.replace("owner=ours", "owner=theirs");
+    fs::write(&path, format!("{}\n{}\n", merged.join("\n"), duplicate)).unwrap();
+    assert!(Pool::open(&path).is_err());
+
+    let output = pmg(&["pool", "fsck", file]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let problems = lines(&output);
+    assert_eq!(problems.len(), 1, "unexpected problems: {:?}", problems);
+    assert!(problems[0].contains("(owner theirs) is also on line"), "unexpected problems: {:?}", problems);
+    assert!(problems[0].contains("(owner ours)"), "unexpected problems: {:?}", problems);
+
+    //So is a merge left with conflict markers in it
+    fs::write(&path, format!("{}\n<<<<<<< HEAD\n", merged.join("\n"))).unwrap();
+    let output = pmg(&["pool", "fsck", file]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn text_pool_header_lines_come_once() {
+    let dir = scratch("header");
+    let path = dir.join("lab.pool");
+    let file = path.to_str().unwrap();
+    let output = pmg(&["pool", "init", file, "-P", "02:5a:10:00:00:00/40", "-b", "text", "-q", "5"]).output().unwrap();
+    assert!(output.status.success(), "init failed: {}", String::from_utf8_lossy(&output.stderr));
+    let header = fs::read_to_string(&path).unwrap();
+
+    //A second quarantine or prefix line is refused rather than the last one winning
+    for (repeated, key) in [("quarantine 60", "quarantine"), ("prefix 02:5a:10:00:00:00/40", "prefix")] {
+        fs::write(&path, format!("{}{}\n", header, repeated)).unwrap();
+        assert!(Pool::open(&path).is_err(), "a repeated {} line was accepted", key);
+
+        let output = pmg(&["pool", "fsck", file]).output().unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(1));
+        assert!(stderr.contains(&format!("line 5: {} must come once", key)), "unexpected error: {}", stderr);
+    }
+
+    fs::write(&path, &header).unwrap();
+    assert!(Pool::open(&path).is_ok());
+    assert!(pmg(&["pool", "fsck", file]).output().unwrap().status.success());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn concurrent_allocations_on_a_text_pool_are_distinct() {
+    let dir = scratch("text-concurrent");
+    let path = dir.join("lab.pool");
+    let output = pmg(&["pool", "init", path.to_str().unwrap(), "-P", "02:5a:10:00:00:00/32", "-b", "text"]).output().unwrap();
+    assert!(output.status.success(), "init failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let workers: Vec<Child> = (0..WORKERS).map(|worker| spawn_worker(&path, worker)).collect();
+    let mut macs = Vec::new();
+    for worker in workers {
+        let output = worker.wait_with_output().unwrap();
+        assert!(output.status.success(), "worker failed: {}", String::from_utf8_lossy(&output.stderr));
+        macs.extend(lines(&output));
+    }
+
+    let distinct: HashSet<&String> = macs.iter().collect();
+    assert_eq!(distinct.len(), WORKERS * ALLOCATIONS, "an address was handed out twice");
+    assert_eq!(Pool::open(&path).unwrap().allocated(), (WORKERS * ALLOCATIONS) as u64);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn csv_and_tsv_keep_awkward_values_whole() {
+    let dir = scratch("records");
+    let path = dir.join("lab.pool");
+    let file = path.to_str().unwrap();
+    init(&path, "02:5a:10:00:00:00/40");
+    pmg(&["pool", "alloc", file, "-o", "vm, 1", "-T", "env=ci", "-T", "team=net", "-C", "say \"hi\"\tnow"]).output().unwrap();
+
+    let output = pmg(&["pool", "list", file, "-F", "csv"]).output().unwrap();
+    let csv = lines(&output);
+    assert_eq!(csv[0], "schema_version,mac,allocated,expires,ttl,owner,tags,comment");
+    assert!(csv[1].ends_with(",,,\"vm, 1\",env=ci team=net,\"say \"\"hi\"\"\tnow\""), "unexpected output: {:?}", csv);
+
+    let output = pmg(&["pool", "find", file, "-F", "tsv"]).output().unwrap();
+    let tsv = lines(&output);
+    assert!(tsv[1].starts_with("1\t02:5a:10:00:00:00\t"), "unexpected output: {:?}", tsv);
+    assert_eq!(tsv[1].split('\t').count(), 8, "unexpected output: {:?}", tsv);
+    assert!(tsv[1].ends_with("\tvm, 1\tenv=ci team=net\tsay \"hi\"\\tnow"), "unexpected output: {:?}", tsv);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
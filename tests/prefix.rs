@@ -0,0 +1,104 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Checks the prefix calculations: contains, overlap, split and summarize.
+
+mod common;
+
+use common::{assert_usage, pmg, pmg_with_input, stdout};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+#[test]
+fn contains_checks_addresses_and_prefixes() {
+    assert_eq!(stdout(&pmg(&["prefix", "contains", "02:5a:10/24", "02-5A-10-FF-FF-FF"])),
+        "02:5a:10:00:00:00/24 contains 02:5a:10:ff:ff:ff\n");
+    assert_eq!(stdout(&pmg(&["prefix", "contains", "02:5a:10/24", "02:5a:10:40:00:00/28"])),
+        "02:5a:10:00:00:00/24 contains 02:5a:10:40:00:00/28\n");
+
+    //A false check exits with 1, and prints no records
+    let output = pmg(&["prefix", "contains", "02:5a:10/24", "02:5a:11:00:00:00"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "02:5a:10:00:00:00/24 does not contain 02:5a:11:00:00:00\n");
+    let output = pmg(&["prefix", "contains", "02:5a:10:40:00:00/28", "02:5a:10/24", "-F", "json"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "[]\n");
+
+    let records = stdout(&pmg(&["prefix", "contains", "02:5a:10/24", "02:5a:10:00:00:01", "-F", "csv"]));
+    assert_eq!(records.lines().nth(1), Some("1,address,02:5a:10:00:00:01,02:5a:10:00:00:00/24,24,02:5a:10:00:00:00,02:5a:10:ff:ff:ff,16777216,1,0"));
+
+    assert_usage(&["prefix", "contains", "02:5a:10/24"], "contains takes a prefix");
+    assert_usage(&["prefix", "contains", "02:5a:10/24", "nope"], "invalid prefix 'nope'");
+}
+
+#[test]
+fn overlap_prints_the_shared_prefix() {
+    assert_eq!(stdout(&pmg(&["prefix", "overlap", "02:5a:10:40:00:00/28", "02:5a:10/24"])),
+        "02:5a:10:40:00:00/28 overlaps 02:5a:10:00:00:00/24\n\
+         Shared Prefix:         02:5a:10:40:00:00/28\n\
+         Shared Addresses:      1048576\n");
+
+    let output = pmg(&["prefix", "overlap", "02:5a:10/24", "02:5a:11/24"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "02:5a:10:00:00:00/24 does not overlap 02:5a:11:00:00:00/24\n");
+}
+
+#[test]
+fn split_divides_into_equal_prefixes() {
+    assert_eq!(stdout(&pmg(&["prefix", "split", "02:5a:10/24"])), "02:5a:10:00:00:00/25\n02:5a:10:80:00:00/25\n");
+
+    let quarters = stdout(&pmg(&["prefix", "split", "02:5a:10/24", "-i", "4"]));
+    assert_eq!(quarters, "02:5a:10:00:00:00/26\n02:5a:10:40:00:00/26\n02:5a:10:80:00:00/26\n02:5a:10:c0:00:00/26\n");
+    assert_eq!(stdout(&pmg(&["prefix", "split", "02:5a:10/24", "-l", "26"])), quarters);
+
+    let split = stdout(&pmg(&["prefix", "split", "02:5a:10/24", "--into=16"]));
+    assert_eq!(split.lines().count(), 16);
+    assert_eq!(split.lines().last(), Some("02:5a:10:f0:00:00/28"));
+
+    //Only a power of two divides a prefix evenly, and the parts must still be prefixes
+    for into in ["3", "0", "1", "12", "four"] {
+        assert_usage(&["prefix", "split", "02:5a:10/24", "-i", into], "expected a power of two");
+    }
+    assert_usage(&["prefix", "split", "02:5a:10:00:00:00/46", "-i", "4"], "cannot be split that way");
+    assert_usage(&["prefix", "split", "02:5a:10/24", "-l", "24"], "cannot be split that way");
+}
+
+#[test]
+fn summarize_collapses_prefixes() {
+    assert_eq!(stdout(&pmg(&["prefix", "summarize", "02:5a:10:80:00:00/25", "02:5a:11/24", "02:5a:10:00:00:00/25", "02:5a:12/24"])),
+        "02:5a:10:00:00:00/23\n02:5a:12:00:00:00/24\n");
+
+    //Prefixes inside another are dropped, and standard input is read when none are typed in
+    let output = pmg_with_input(&["prefix", "summarize"], "# lab\n02:5a:10/24\n\n02:5a:10:40:00:00/28\n02:5a:11/24\n");
+    assert_eq!(stdout(&output), "02:5a:10:00:00:00/23\n");
+
+    let records = stdout(&pmg(&["prefix", "summarize", "02:5a:10/24", "02:5a:11/24", "-F", "tsv"]));
+    assert_eq!(records.lines().nth(1), Some("1\tprefix\t\t02:5a:10:00:00:00/23\t23\t02:5a:10:00:00:00\t02:5a:11:ff:ff:ff\t33554432\t1\t0"));
+}
+
+#[test]
+fn input_that_cannot_be_read_is_a_failure() {
+    //Bytes that are not UTF-8 fail the read, which exits with 1 rather than as a usage error
+    let mut child = Command::new(env!("CARGO_BIN_EXE_pmg"))
+        .args(["prefix", "summarize"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"02:5a:10/24\n\xff\xfe\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("pmg: "));
+}
@@ -0,0 +1,81 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Checks where pmg range starts and stops a listing: -o/--start as an offset or an address,
+//-m/--limit, and the cursor printed by -v/--verbose.
+
+mod common;
+
+use common::{assert_usage, pmg, stdout};
+
+#[test]
+fn start_and_limit_pick_a_slice() {
+    assert_eq!(stdout(&pmg(&["range", "02:5a:10/24", "-L", "-o", "1000", "-m", "3"])),
+        "02:5a:10:00:03:e8\n02:5a:10:00:03:e9\n02:5a:10:00:03:ea\n");
+
+    //An address starts from its own offset
+    assert_eq!(stdout(&pmg(&["range", "02:5a:10/24", "-L", "-o", "02-5A-10-00-03-E8", "-m", "3"])),
+        stdout(&pmg(&["range", "02:5a:10/24", "-L", "-o", "1000", "-m", "3"])));
+
+    //Twelve digits are an address even when every digit is decimal
+    assert_eq!(stdout(&pmg(&["range", "02:50:10/24", "-L", "-o", "025010000001", "-m", "2"])),
+        "02:50:10:00:00:01\n02:50:10:00:00:02\n");
+
+    //The listing stops at the end of the prefix whatever the limit
+    assert_eq!(stdout(&pmg(&["range", "02:5a:10:00:00:00/44", "-L", "-o", "14", "-m", "10"])),
+        "02:5a:10:00:00:0e\n02:5a:10:00:00:0f\n");
+    assert_eq!(stdout(&pmg(&["range", "02:5a:10:00:00:00/44", "-L"])).lines().count(), 16);
+    assert_eq!(stdout(&pmg(&["range", "02:5a:10/24", "-L", "-m", "0"])), "");
+}
+
+#[test]
+fn starts_outside_the_prefix_are_refused() {
+    assert_usage(&["range", "02:5a:10:00:00:00/44", "-L", "-o", "16"],
+        "the start offset 16 is past the end of 02:5a:10:00:00:00/44, which has 16 addresses");
+    assert_usage(&["range", "02:5a:10:00:00:00/44", "-x", "-k", "1", "-o", "16"], "is past the end");
+    assert_usage(&["range", "02:5a:10/24", "-L", "-o", "02:5a:11:00:00:00"],
+        "the start address 02:5a:11:00:00:00 is not inside 02:5a:10:00:00:00/24");
+    assert_usage(&["range", "02:5a:10/24", "-L", "-o", "first"], "invalid -o/--start 'first'");
+    assert_usage(&["range", "02:5a:10/24", "-L", "-m", "many"], "invalid -m/--limit 'many'");
+
+    //Without a listing there is nothing to start or stop
+    assert_usage(&["range", "02:5a:10/24", "-o", "5"], "-o/--start and -m/--limit pick addresses from a -L/--list or -x/--shuffle");
+    assert_usage(&["range", "02:5a:10/24", "-m", "5"], "-o/--start and -m/--limit pick addresses from a -L/--list or -x/--shuffle");
+}
+
+#[test]
+fn verbose_prints_the_next_cursor() {
+    let output = pmg(&["range", "02:5a:10/24", "-L", "-o", "1000", "-m", "3", "-v"]);
+    assert_eq!(stdout(&output).lines().count(), 3);
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "Next Cursor:           1003\n");
+
+    //At the end of the prefix the cursor is the number of addresses
+    let output = pmg(&["range", "02:5a:10:00:00:00/44", "-L", "-o", "10", "-v"]);
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "Next Cursor:           16\n");
+}
+
+#[test]
+fn shuffled_listings_resume_from_the_cursor() {
+    let whole = stdout(&pmg(&["range", "02:5a:10/24", "-x", "-k", "42", "-m", "8"]));
+    let first = pmg(&["range", "02:5a:10/24", "-x", "-k", "42", "-m", "4", "-v"]);
+    assert_eq!(String::from_utf8_lossy(&first.stderr), "Next Cursor:           4\n");
+    let rest = stdout(&pmg(&["range", "02:5a:10/24", "-x", "-k", "42", "-o", "4", "-m", "4"]));
+    assert_eq!(format!("{}{}", stdout(&first), rest), whole);
+
+    //An address starts from wherever it falls in the shuffled order
+    let fifth = whole.lines().nth(5).unwrap();
+    let output = pmg(&["range", "02:5a:10/24", "-x", "-k", "42", "-o", fifth, "-m", "3", "-v"]);
+    assert_eq!(stdout(&output), whole.lines().skip(5).map(|line| format!("{}\n", line)).collect::<String>());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "Next Cursor:           8\n");
+}
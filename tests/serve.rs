@@ -0,0 +1,244 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Checks the JSON API of pmg serve.  Each test starts a server on a free port of
+//localhost and talks to it over plain HTTP.
+
+extern crate pmg;
+
+use pmg::{MacAddress, Pool};
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+
+//How many clients allocate at the same moment
+const CLIENTS: usize = 16;
+
+//How many addresses each client allocates
+const ALLOCATIONS: usize = 8;
+
+//A running server, stopped when dropped
+struct Server {
+    child: Child,
+    address: String,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+//Creates an empty scratch directory for a test
+fn scratch(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("pmg-serve-test-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn init(path: &Path, prefix: &str) {
+    let output = Command::new(env!("CARGO_BIN_EXE_pmg"))
+        .args(["pool", "init", path.to_str().unwrap(), "-P", prefix])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "init failed: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+//Starts a server on a port picked by the system and waits until it is listening
+fn serve(path: &Path) -> Server {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_pmg"))
+        .args(["serve", "-l", "127.0.0.1:0", "-p", path.to_str().unwrap()])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap()).read_line(&mut line).unwrap();
+    let address = line.trim().rsplit("http://").next().unwrap().to_string();
+    assert!(!address.is_empty(), "the server did not say where it is listening");
+
+    Server { child, address }
+}
+
+//Sends one request and returns the status and body of the response
+fn request(server: &Server, method: &str, path: &str, body: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(&server.address).unwrap();
+    write!(stream, "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\n\r\n{}",
+        method, path, server.address, body.len(), body).unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let status = response.split(' ').nth(1).and_then(|status| status.parse().ok()).unwrap();
+    let body = response.split("\r\n\r\n").nth(1).unwrap_or("").trim().to_string();
+    (status, body)
+}
+
+//Returns the text of a field of a JSON response.  Good enough for the flat fields tested.
+fn field(body: &str, name: &str) -> Option<String> {
+    let start = body.find(&format!("\"{}\":", name))? + name.len() + 3;
+    let rest = &body[start..];
+    if let Some(rest) = rest.strip_prefix('"') {
+        rest.find('"').map(|end| rest[..end].to_string())
+    } else {
+        rest.find([',', '}']).map(|end| rest[..end].to_string())
+    }
+}
+
+#[test]
+fn concurrent_allocations_are_distinct() {
+    let dir = scratch("concurrent");
+    let path = dir.join("lab.pool");
+    init(&path, "02:5a:10:00:00:00/32");
+    let server = serve(&path);
+
+    let macs: Vec<String> = thread::scope(|scope| {
+        let clients: Vec<_> = (0..CLIENTS)
+            .map(|client| {
+                let server = &server;
+                scope.spawn(move || {
+                    let body = format!("{{\"owner\": \"client-{}\"}}", client);
+                    (0..ALLOCATIONS)
+                        .map(|_| {
+                            let (status, response) = request(server, "POST", "/v1/allocations", &body);
+                            assert_eq!(status, 201, "{}", response);
+                            field(&response, "mac").unwrap()
+                        })
+                        .collect::<Vec<String>>()
+                })
+            })
+            .collect();
+
+        clients.into_iter().flat_map(|client| client.join().unwrap()).collect()
+    });
+
+    let distinct: HashSet<&String> = macs.iter().collect();
+    assert_eq!(macs.len(), CLIENTS * ALLOCATIONS);
+    assert_eq!(distinct.len(), macs.len(), "an address was handed out twice");
+
+    let pool = Pool::open(&path).unwrap();
+    assert_eq!(pool.allocated(), (CLIENTS * ALLOCATIONS) as u64);
+    for mac in &macs {
+        assert!(pool.is_allocated(&mac.parse::<MacAddress>().unwrap()));
+    }
+}
+
+#[test]
+fn allocate_lookup_renew_and_release() {
+    let dir = scratch("lifecycle");
+    let path = dir.join("lab.pool");
+    init(&path, "02:5a:10:00:00:00/40");
+    let server = serve(&path);
+
+    let (status, body) = request(&server, "POST", "/v1/allocations",
+        "{\"owner\": \"vm-42\", \"tags\": {\"rack\": \"r1\"}, \"comment\": \"build box\", \"ttl\": \"2h\"}");
+    assert_eq!(status, 201, "{}", body);
+    let mac = field(&body, "mac").unwrap();
+    assert_eq!(field(&body, "owner").unwrap(), "vm-42");
+    assert_eq!(field(&body, "ttl").unwrap(), "7200");
+
+    let (status, body) = request(&server, "GET", &format!("/v1/allocations/{}", mac), "");
+    assert_eq!(status, 200, "{}", body);
+    assert_eq!(field(&body, "comment").unwrap(), "build box");
+
+    let (status, body) = request(&server, "GET", "/v1/allocations?tag=rack%3Dr1", "");
+    assert_eq!(status, 200, "{}", body);
+    assert!(body.contains(&mac), "{}", body);
+    let (status, body) = request(&server, "GET", "/v1/allocations?owner=nobody", "");
+    assert_eq!((status, body.as_str()), (200, "[]"));
+
+    let (status, body) = request(&server, "POST", &format!("/v1/allocations/{}/renew", mac), "{\"ttl\": 60}");
+    assert_eq!(status, 200, "{}", body);
+    assert_eq!(field(&body, "ttl").unwrap(), "60");
+
+    let (status, body) = request(&server, "GET", "/v1/stats", "");
+    assert_eq!(status, 200, "{}", body);
+    assert_eq!(field(&body, "total").unwrap(), "256");
+    assert_eq!(field(&body, "allocated").unwrap(), "1");
+    assert_eq!(field(&body, "free").unwrap(), "255");
+
+    let (status, body) = request(&server, "DELETE", &format!("/v1/allocations/{}", mac), "");
+    assert_eq!(status, 200, "{}", body);
+    let (status, _) = request(&server, "GET", &format!("/v1/allocations/{}", mac), "");
+    assert_eq!(status, 404);
+    let (status, _) = request(&server, "DELETE", &format!("/v1/allocations/{}", mac), "");
+    assert_eq!(status, 404);
+}
+
+#[test]
+fn full_pool_is_a_conflict() {
+    let dir = scratch("full");
+    let path = dir.join("lab.pool");
+    init(&path, "02:5a:10:00:00:00/47");
+    let server = serve(&path);
+
+    for _ in 0..2 {
+        assert_eq!(request(&server, "POST", "/v1/allocations", "").0, 201);
+    }
+    assert_eq!(request(&server, "POST", "/v1/allocations", "").0, 409);
+}
+
+#[test]
+fn generate_stays_inside_the_prefix() {
+    let dir = scratch("generate");
+    let path = dir.join("lab.pool");
+    init(&path, "02:5a:10:00:00:00/40");
+    let server = serve(&path);
+
+    let (status, body) = request(&server, "GET", "/v1/generate?count=5&prefix=02:aa:bb/24", "");
+    assert_eq!(status, 200, "{}", body);
+    assert_eq!(body.matches("\"02:aa:bb:").count(), 5, "{}", body);
+
+    assert_eq!(request(&server, "GET", "/v1/generate?count=0", "").0, 400);
+    assert_eq!(request(&server, "GET", "/v1/generate?count=100000", "").0, 400);
+    assert_eq!(request(&server, "GET", "/v1/generate?prefix=zz", "").0, 400);
+
+    //Universally administered and multicast prefixes are refused, as gen refuses them
+    //without -f/--force
+    let (status, body) = request(&server, "GET", "/v1/generate?prefix=00:1b:21/24", "");
+    assert_eq!(status, 400, "{}", body);
+    assert!(body.contains("not locally administered unicast"), "{}", body);
+    assert_eq!(request(&server, "GET", "/v1/generate?prefix=03:aa:bb/24", "").0, 400);
+}
+
+#[test]
+fn bad_requests_are_rejected() {
+    let dir = scratch("bad");
+    let path = dir.join("lab.pool");
+    init(&path, "02:5a:10:00:00:00/40");
+    let server = serve(&path);
+
+    let (status, body) = request(&server, "POST", "/v1/allocations", "{\"owner\": ");
+    assert_eq!(status, 400);
+    assert!(field(&body, "error").unwrap().starts_with("invalid JSON"), "{}", body);
+
+    assert_eq!(request(&server, "POST", "/v1/allocations", "{\"owner\": 7}").0, 400);
+    assert_eq!(request(&server, "POST", "/v1/allocations", "{\"ttl\": 0}").0, 400);
+    assert_eq!(request(&server, "POST", "/v1/allocations", "{\"tags\": [\"rack\"]}").0, 400);
+    assert_eq!(request(&server, "GET", "/v1/allocations/not-a-mac", "").0, 400);
+    assert_eq!(request(&server, "GET", "/v1/allocations/0a:00:00:00:00:00", "").0, 400);
+    assert_eq!(request(&server, "PUT", "/v1/stats", "").0, 405);
+    assert_eq!(request(&server, "GET", "/v2/stats", "").0, 404);
+
+    //Nothing was allocated by any of them
+    assert_eq!(Pool::open(&path).unwrap().allocated(), 0);
+}
@@ -0,0 +1,87 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Checks the keyed shuffle of a prefix: every address exactly once, in an order that can be
+//resumed from its cursor and looked up by address.
+
+extern crate pmg;
+
+use pmg::{MacAddress, MacPrefix};
+use std::collections::HashSet;
+
+fn prefix(value: &str) -> MacPrefix {
+    value.parse().unwrap()
+}
+
+#[test]
+fn every_address_is_visited_once() {
+    //An odd number of host bits makes the walk skip values past the end of the block
+    for value in ["02:5a:10:00:00:00/40", "02:5a:10:00:00:00/41", "02:5a:10:00:00:00/36", "02:5a:10:00:00:00/47"] {
+        let prefix = prefix(value);
+        for key in 0..4 {
+            let order: Vec<MacAddress> = prefix.shuffled(key).collect();
+            assert_eq!(order.len() as u64, prefix.count(), "{} key {}", value, key);
+            assert!(order.iter().all(|mac| prefix.contains(mac)), "{} key {}", value, key);
+            assert_eq!(order.iter().collect::<HashSet<_>>().len() as u64, prefix.count(), "{} key {} repeated an address", value, key);
+        }
+    }
+}
+
+#[test]
+fn the_key_decides_the_order() {
+    let prefix = prefix("02:5a:10:00:00:00/36");
+    let first: Vec<MacAddress> = prefix.shuffled(7).take(64).collect();
+    assert_eq!(first, prefix.shuffled(7).take(64).collect::<Vec<_>>());
+    assert_ne!(first, prefix.shuffled(8).take(64).collect::<Vec<_>>());
+    assert_ne!(first, prefix.addresses().take(64).collect::<Vec<_>>());
+}
+
+#[test]
+fn the_cursor_resumes_the_walk() {
+    let prefix = prefix("02:5a:10:00:00:00/40");
+    let order: Vec<MacAddress> = prefix.shuffled(42).collect();
+
+    let mut shuffle = prefix.shuffled(42);
+    let head: Vec<MacAddress> = shuffle.by_ref().take(100).collect();
+    assert_eq!(shuffle.cursor(), 100);
+    assert_eq!(head[..], order[..100]);
+
+    //A fresh shuffle skipped to the cursor carries on where the first one stopped
+    let cursor = shuffle.cursor() as usize;
+    let rest: Vec<MacAddress> = prefix.shuffled(42).skip(cursor).collect();
+    assert_eq!(rest[..], order[100..]);
+    assert_eq!(rest, shuffle.collect::<Vec<_>>());
+
+    let mut shuffle = prefix.shuffled(42);
+    assert_eq!(shuffle.nth(200), Some(order[200]));
+    assert_eq!(shuffle.cursor(), 201);
+    assert_eq!(shuffle.size_hint(), (55, Some(55)));
+    assert_eq!(shuffle.nth(1000), None);
+    assert_eq!(shuffle.cursor(), 256);
+}
+
+#[test]
+fn position_finds_each_address() {
+    for value in ["02:5a:10:00:00:00/40", "02:5a:10:00:00:00/41"] {
+        let prefix = prefix(value);
+        let shuffle = prefix.shuffled(99);
+        for (index, mac) in prefix.shuffled(99).enumerate() {
+            assert_eq!(shuffle.position(&mac), Some(index as u64), "{} {}", value, mac);
+        }
+    }
+
+    let shuffle = prefix("02:5a:10:00:00:00/40").shuffled(99);
+    assert_eq!(shuffle.position(&"02:5a:10:00:01:00".parse().unwrap()), None);
+    assert_eq!(shuffle.position(&"02:5a:0f:ff:ff:ff".parse().unwrap()), None);
+}
@@ -0,0 +1,279 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Checks the Unix socket mode of pmg serve.  Each test starts a server on a socket in
+//its own scratch directory and sends it lines of JSON.
+
+#![cfg(unix)]
+
+extern crate libc;
+extern crate pmg;
+
+use pmg::{MacAddress, Pool};
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+
+//How many clients allocate at the same moment
+const CLIENTS: usize = 8;
+
+//How many addresses each client allocates
+const ALLOCATIONS: usize = 8;
+
+//A running server, stopped when dropped
+struct Server {
+    child: Child,
+    socket: PathBuf,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+//A connection that sends requests one line at a time
+struct Client {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl Client {
+    fn connect(server: &Server) -> Client {
+        let writer = UnixStream::connect(&server.socket).unwrap();
+        Client {
+            reader: BufReader::new(writer.try_clone().unwrap()),
+            writer,
+        }
+    }
+
+    //Sends one request line and returns the response line
+    fn send(&mut self, request: &str) -> String {
+        writeln!(self.writer, "{}", request).unwrap();
+
+        let mut response = String::new();
+        self.reader.read_line(&mut response).unwrap();
+        assert!(response.ends_with('\n'), "the response is not a line: {:?}", response);
+        response.trim().to_string()
+    }
+}
+
+//Creates an empty scratch directory for a test
+fn scratch(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("pmg-socket-test-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn init(path: &Path, prefix: &str) {
+    let output = Command::new(env!("CARGO_BIN_EXE_pmg"))
+        .args(["pool", "init", path.to_str().unwrap(), "-P", prefix])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "init failed: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+//Starts a server and waits until it is listening
+fn serve(path: &Path, socket: &Path) -> Server {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_pmg"))
+        .args(["serve", "-u", socket.to_str().unwrap(), "-p", path.to_str().unwrap()])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap()).read_line(&mut line).unwrap();
+    assert!(line.starts_with("Listening on "), "the server did not start: {:?}", line);
+
+    Server { child, socket: socket.to_path_buf() }
+}
+
+//Returns the text of a field of a JSON response.  Good enough for the flat fields tested.
+fn field(body: &str, name: &str) -> Option<String> {
+    let start = body.find(&format!("\"{}\":", name))? + name.len() + 3;
+    let rest = &body[start..];
+    if let Some(rest) = rest.strip_prefix('"') {
+        rest.find('"').map(|end| rest[..end].to_string())
+    } else {
+        rest.find([',', '}']).map(|end| rest[..end].to_string())
+    }
+}
+
+fn uid() -> u32 {
+    //SAFETY: getuid cannot fail and has no side effects
+    unsafe { libc::getuid() }
+}
+
+#[test]
+fn allocations_record_the_peer_uid() {
+    let dir = scratch("uid");
+    let path = dir.join("lab.pool");
+    init(&path, "02:5a:10:00:00:00/40");
+    let server = serve(&path, &dir.join("pmg.sock"));
+    let mut client = Client::connect(&server);
+
+    //A uid tag sent by the client is replaced by the one the kernel reports
+    let response = client.send("{\"action\": \"alloc\", \"owner\": \"vm-42\", \"tags\": {\"uid\": \"0\", \"env\": \"ci\"}}");
+    assert_eq!(field(&response, "ok").unwrap(), "true", "{}", response);
+    let mac = field(&response, "mac").unwrap();
+    assert_eq!(field(&response, "uid").unwrap(), uid().to_string());
+
+    let pool = Pool::open(&path).unwrap();
+    let allocation = pool.get(&mac.parse::<MacAddress>().unwrap()).unwrap();
+    assert_eq!(allocation.metadata.tags["uid"], uid().to_string());
+    assert_eq!(allocation.metadata.tags["env"], "ci");
+
+    let response = client.send(&format!("{{\"action\": \"find\", \"tags\": {{\"uid\": \"{}\"}}}}", uid()));
+    assert!(response.contains(&mac), "{}", response);
+}
+
+#[test]
+fn every_pool_action_is_available() {
+    let dir = scratch("actions");
+    let path = dir.join("lab.pool");
+    init(&path, "02:5a:10:00:00:00/40");
+    let server = serve(&path, &dir.join("pmg.sock"));
+    let mut client = Client::connect(&server);
+
+    let response = client.send("{\"action\": \"alloc\", \"ttl\": \"2h\", \"comment\": \"build box\"}");
+    let mac = field(&response, "mac").unwrap();
+    assert_eq!(field(&response, "ttl").unwrap(), "7200");
+
+    let response = client.send(&format!("{{\"action\": \"renew\", \"mac\": \"{}\", \"ttl\": 60}}", mac));
+    assert_eq!(field(&response, "ttl").unwrap(), "60", "{}", response);
+
+    let response = client.send(&format!("{{\"action\": \"show\", \"mac\": \"{}\"}}", mac));
+    assert_eq!(field(&response, "comment").unwrap(), "build box", "{}", response);
+
+    let response = client.send("{\"action\": \"list\"}");
+    assert!(response.contains(&mac), "{}", response);
+
+    let response = client.send("{\"action\": \"stats\"}");
+    assert_eq!(field(&response, "allocated").unwrap(), "1", "{}", response);
+
+    let response = client.send("{\"action\": \"reap\"}");
+    assert_eq!(response, "{\"ok\":true,\"result\":[]}");
+
+    let response = client.send("{\"action\": \"generate\", \"count\": 3, \"prefix\": \"02:aa:bb/24\"}");
+    assert_eq!(response.matches("\"02:aa:bb:").count(), 3, "{}", response);
+
+    let response = client.send(&format!("{{\"action\": \"release\", \"mac\": \"{}\"}}", mac));
+    assert_eq!(field(&response, "ok").unwrap(), "true", "{}", response);
+    assert_eq!(Pool::open(&path).unwrap().allocated(), 0);
+}
+
+#[test]
+fn errors_keep_the_connection_open() {
+    let dir = scratch("errors");
+    let path = dir.join("lab.pool");
+    init(&path, "02:5a:10:00:00:00/40");
+    let server = serve(&path, &dir.join("pmg.sock"));
+    let mut client = Client::connect(&server);
+
+    let response = client.send("{\"action\": ");
+    assert_eq!(field(&response, "ok").unwrap(), "false");
+    assert!(field(&response, "error").unwrap().starts_with("invalid JSON"), "{}", response);
+
+    assert_eq!(field(&client.send("{}"), "status").unwrap(), "400");
+    assert_eq!(field(&client.send("{\"action\": \"launch\"}"), "status").unwrap(), "404");
+    assert_eq!(field(&client.send("{\"action\": \"release\"}"), "status").unwrap(), "400");
+    assert_eq!(field(&client.send("{\"action\": \"release\", \"mac\": \"02:5a:10:00:00:07\"}"), "status").unwrap(), "404");
+
+    //A prefix that is not a string is refused rather than generating over the whole space
+    let response = client.send("{\"action\": \"generate\", \"prefix\": 5}");
+    assert_eq!(field(&response, "status").unwrap(), "400", "{}", response);
+    assert_eq!(field(&response, "error").unwrap(), "prefix must be a string");
+    assert_eq!(field(&client.send("{\"action\": \"generate\", \"prefix\": [\"02:aa:bb/24\"]}"), "status").unwrap(), "400");
+
+    let response = client.send("{\"action\": \"alloc\"}");
+    assert_eq!(field(&response, "ok").unwrap(), "true", "{}", response);
+}
+
+#[test]
+fn concurrent_clients_get_distinct_addresses() {
+    let dir = scratch("concurrent");
+    let path = dir.join("lab.pool");
+    init(&path, "02:5a:10:00:00:00/32");
+    let server = serve(&path, &dir.join("pmg.sock"));
+
+    let macs: Vec<String> = thread::scope(|scope| {
+        let clients: Vec<_> = (0..CLIENTS)
+            .map(|_| {
+                let server = &server;
+                scope.spawn(move || {
+                    let mut client = Client::connect(server);
+                    (0..ALLOCATIONS)
+                        .map(|_| field(&client.send("{\"action\": \"alloc\"}"), "mac").unwrap())
+                        .collect::<Vec<String>>()
+                })
+            })
+            .collect();
+
+        clients.into_iter().flat_map(|client| client.join().unwrap()).collect()
+    });
+
+    let distinct: HashSet<&String> = macs.iter().collect();
+    assert_eq!(distinct.len(), CLIENTS * ALLOCATIONS, "an address was handed out twice");
+}
+
+#[test]
+fn stale_socket_is_replaced() {
+    let dir = scratch("stale");
+    let path = dir.join("lab.pool");
+    init(&path, "02:5a:10:00:00:00/40");
+
+    //A listener that has gone away leaves its socket file behind
+    let socket = dir.join("pmg.sock");
+    drop(UnixListener::bind(&socket).unwrap());
+    assert!(socket.exists());
+
+    let server = serve(&path, &socket);
+    let mut client = Client::connect(&server);
+    assert_eq!(field(&client.send("{\"action\": \"stats\"}"), "ok").unwrap(), "true");
+
+    //A socket with a server behind it is never taken over
+    let output = Command::new(env!("CARGO_BIN_EXE_pmg"))
+        .args(["serve", "-u", socket.to_str().unwrap(), "-p", path.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(field(&client.send("{\"action\": \"stats\"}"), "ok").unwrap(), "true");
+}
+
+#[test]
+fn audit_log_records_the_peer_uid() {
+    let dir = scratch("audit");
+    let path = dir.join("lab.pool");
+    init(&path, "02:5a:10:00:00:00/40");
+    let server = serve(&path, &dir.join("pmg.sock"));
+    let mut client = Client::connect(&server);
+
+    let mac = field(&client.send("{\"action\": \"alloc\"}"), "mac").unwrap();
+    client.send(&format!("{{\"action\": \"release\", \"mac\": \"{}\"}}", mac));
+
+    let log = fs::read_to_string(dir.join("lab.pool.log")).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines.len(), 3, "unexpected log: {}", log);
+    for line in &lines[1..] {
+        assert!(line.ends_with(&format!("\"user\":\"uid:{}\"}}", uid())), "unexpected log: {}", log);
+    }
+}
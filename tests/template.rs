@@ -0,0 +1,96 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Checks every field and modifier of output templates, and the templates that are refused.
+
+extern crate pmg;
+
+use pmg::{Case, MacAddress, MacPrefix, Notation, Template, TemplateError};
+use std::process::Command;
+
+fn render(template: &str, notation: &Notation, prefix: Option<&str>, index: u64) -> String {
+    let template: Template = template.parse().unwrap();
+    let mac: MacAddress = "02:5a:10:ab:cd:ef".parse().unwrap();
+    let prefix: Option<MacPrefix> = prefix.map(|prefix| prefix.parse().unwrap());
+
+    let mut line = String::new();
+    template.write(&mut line, notation, &mac, prefix.as_ref(), index);
+    line
+}
+
+#[test]
+fn fields_fill_in_from_the_address() {
+    let notation = Notation::default();
+    assert_eq!(render("{mac}", &notation, None, 0), "02:5a:10:ab:cd:ef");
+    assert_eq!(render("{eui64}", &notation, None, 0), "02:5a:10:ff:fe:ab:cd:ef");
+    assert_eq!(render("{eui64:modified}", &notation, None, 0), "00:5a:10:ff:fe:ab:cd:ef");
+    assert_eq!(render("{oui} {nic}", &notation, None, 0), "02:5a:10 ab:cd:ef");
+    assert_eq!(render("{octet1}{octet6}", &notation, None, 0), "02ef");
+    assert_eq!(render("{int}", &notation, None, 0), "2585850007023");
+    assert_eq!(render("{index} {index:1} {index:100}", &notation, None, 7), "7 8 107");
+}
+
+#[test]
+fn prefix_fields_are_empty_without_a_prefix() {
+    let notation = Notation::default();
+    assert_eq!(render("{prefix} {first} {last} {length} {count}", &notation, Some("02:5a:10/24"), 0),
+        "02:5a:10:00:00:00/24 02:5a:10:00:00:00 02:5a:10:ff:ff:ff 24 16777216");
+    assert_eq!(render("[{prefix}{first}{last}{length}{count}]", &notation, None, 0), "[]");
+}
+
+#[test]
+fn modifiers_override_the_notation() {
+    let notation = Notation::cisco(Case::Upper);
+    assert_eq!(render("{mac}", &notation, None, 0), "025A.10AB.CDEF");
+    assert_eq!(render("{mac:lower}", &notation, None, 0), "025a.10ab.cdef");
+    assert_eq!(render("{mac:-}", &notation, None, 0), "02-5A-10-AB-CD-EF");
+    assert_eq!(render("{mac:colon:lower}", &notation, None, 0), "02:5a:10:ab:cd:ef");
+    assert_eq!(render("{mac:none}", &notation, None, 0), "025A10ABCDEF");
+    assert_eq!(render("{mac:_:g3}", &notation, None, 0), "025A10_ABCDEF");
+    assert_eq!(render("{eui64:modified:g2:lower:colon}", &notation, None, 0), "005a:10ff:feab:cdef");
+    assert_eq!(render("{{{mac:none}}}", &notation, None, 0), "{025A10ABCDEF}");
+}
+
+#[test]
+fn bad_templates_are_refused() {
+    assert_eq!("{mac".parse::<Template>(), Err(TemplateError::Unclosed(0)));
+    assert_eq!("mac}".parse::<Template>(), Err(TemplateError::Unopened(3)));
+    assert_eq!("{ip}".parse::<Template>(), Err(TemplateError::UnknownField("ip".to_string())));
+    assert_eq!("{octet7}".parse::<Template>(), Err(TemplateError::UnknownField("octet7".to_string())));
+    assert_eq!("{int:upper}".parse::<Template>(), Err(TemplateError::InvalidModifier("int".to_string(), "upper".to_string())));
+    assert_eq!("{mac:modified}".parse::<Template>(), Err(TemplateError::InvalidModifier("mac".to_string(), "modified".to_string())));
+    assert_eq!("{mac:g9}".parse::<Template>(), Err(TemplateError::InvalidModifier("mac".to_string(), "g9".to_string())));
+    assert_eq!("{index:one}".parse::<Template>(), Err(TemplateError::InvalidModifier("index".to_string(), "one".to_string())));
+    assert_eq!("{mac::}".parse::<Template>(), Err(TemplateError::InvalidModifier("mac".to_string(), "".to_string())));
+}
+
+#[test]
+fn commands_print_through_the_template() {
+    let output = Command::new(env!("CARGO_BIN_EXE_pmg"))
+        .args(["range", "02:5a:10:00:00:00/40", "-L", "-o", "9", "-m", "2", "-t", "host-{index:1} \"{mac:upper:none}\""])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "host-10 \"025A10000009\"\nhost-11 \"025A1000000A\"\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_pmg"))
+        .args(["-S", "1", "-u", "-N", "4", "-P", "02:5a:10", "-t", "{index},{prefix}"])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout),
+        "0,02:5a:10:00:00:00/24\n1,02:5a:10:00:00:00/24\n2,02:5a:10:00:00:00/24\n3,02:5a:10:00:00:00/24\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_pmg")).args(["-u", "-t", "{mac}", "-F", "csv"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+}
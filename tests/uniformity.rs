@@ -0,0 +1,112 @@
+//Copyright 2017 Paul Hill
+//
+//Licensed under the Apache License, Version 2.0 (the "License");
+//you may not use this file except in compliance with the License.
+//You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//Unless required by applicable law or agreed to in writing, software
+//distributed under the License is distributed on an "AS IS" BASIS,
+//WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//See the License for the specific language governing permissions and
+//limitations under the License.
+
+//Statistical checks that the generator covers the whole locally administered
+//unicast space evenly.  A fixed seed keeps the checks deterministic.
+
+extern crate pmg;
+
+use pmg::{Generator, MacAddress, SeededRng};
+
+//How many addresses each check draws
+const SAMPLES: usize = 256 * 1024;
+
+//Chi-squared critical values at a significance of 0.0001 for 63 and 255 degrees of freedom
+const CRITICAL_63: f64 = 113.0;
+const CRITICAL_255: f64 = 340.0;
+
+fn sample(seed: u64) -> Vec<MacAddress> {
+    let mut generator = Generator::new(SeededRng::new(seed));
+    (0..SAMPLES).map(|_| generator.address().unwrap()).collect()
+}
+
+//Pearson's chi-squared statistic for counts that should all equal the mean
+fn chi_squared(counts: &[u64]) -> f64 {
+    let expected = counts.iter().sum::<u64>() as f64 / counts.len() as f64;
+    counts.iter().map(|&c| (c as f64 - expected).powi(2) / expected).sum()
+}
+
+#[test]
+fn every_address_is_local_unicast() {
+    for mac in sample(1) {
+        assert!(mac.is_local(), "{} is not locally administered", mac);
+        assert!(!mac.is_multicast(), "{} is not unicast", mac);
+    }
+}
+
+#[test]
+fn first_octet_is_uniform_over_its_free_bits() {
+    //Only the upper six bits of the first octet are random
+    let mut counts = vec![0u64; 64];
+    for mac in sample(2) {
+        counts[(mac.octets()[0] >> 2) as usize] += 1;
+    }
+
+    assert!(counts.iter().all(|&c| c > 0));
+    let statistic = chi_squared(&counts);
+    assert!(statistic < CRITICAL_63, "first octet chi-squared {}", statistic);
+}
+
+#[test]
+fn remaining_octets_are_uniform() {
+    let macs = sample(3);
+
+    for position in 1..6 {
+        let mut counts = vec![0u64; 256];
+        for mac in &macs {
+            counts[mac.octets()[position] as usize] += 1;
+        }
+
+        //Every value, including those with a zero nibble, must appear
+        assert!(counts.iter().all(|&c| c > 0), "octet {} misses a value", position);
+        let statistic = chi_squared(&counts);
+        assert!(statistic < CRITICAL_255, "octet {} chi-squared {}", position, statistic);
+    }
+}
+
+#[test]
+fn every_free_bit_is_balanced() {
+    let macs = sample(4);
+    let expected = SAMPLES as f64 / 2.0;
+    //Five standard deviations of a binomial with p = 0.5
+    let tolerance = 5.0 * (SAMPLES as f64 / 4.0).sqrt();
+
+    //Bits are counted from the most significant bit of the first octet
+    for bit in 0..48 {
+        //Skip the U/L and I/G bits, which are fixed
+        if bit == 6 || bit == 7 {
+            continue;
+        }
+
+        let ones = macs.iter()
+            .filter(|&&mac| (u64::from(mac) >> (47 - bit)) & 1 == 1)
+            .count() as f64;
+        assert!((ones - expected).abs() < tolerance, "bit {} was set {} times", bit, ones);
+    }
+}
+
+#[test]
+fn prefixes_are_local_unicast_and_uniform() {
+    let mut generator = Generator::new(SeededRng::new(5));
+    let mut counts = vec![0u64; 64];
+
+    for _ in 0..SAMPLES {
+        let prefix = generator.prefix(3).unwrap();
+        assert!(prefix.first().is_local() && !prefix.first().is_multicast());
+        counts[(prefix.octets()[0] >> 2) as usize] += 1;
+    }
+
+    let statistic = chi_squared(&counts);
+    assert!(statistic < CRITICAL_63, "prefix first octet chi-squared {}", statistic);
+}